@@ -7,10 +7,11 @@ use avian3d::{parry::shape::Shape, prelude::*};
 use bevy_app::prelude::*;
 use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::{entity_disabling::Disabled, prelude::*};
-use bevy_platform::collections::HashMap;
+use bevy_platform::collections::{HashMap, HashSet};
 use bevy_reflect::prelude::*;
 use bevy_steam_audio::{
-    STEAM_AUDIO_CONTEXT, SteamAudioSystems,
+    STEAM_AUDIO_CONTEXT, SteamAudioSchedule, SteamAudioSystems,
+    error::MeshConversionError,
     prelude::*,
     scene::{
         InSteamAudioMeshSpawnQueue, SteamAudioInstancedMesh, SteamAudioRootScene,
@@ -50,7 +51,7 @@ impl Plugin for AvianSteamAudioScenePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<AvianSteamAudioSettings>();
         app.add_systems(
-            PostUpdate,
+            SteamAudioSchedule::from_app(app),
             (
                 // if we modified or removed a mesh, first despawn it on steam audio's side
                 garbage_collect_meshes,
@@ -61,10 +62,13 @@ impl Plugin for AvianSteamAudioScenePlugin {
                 spawn_new_steam_audio_meshes,
             )
                 .chain()
-                .in_set(SteamAudioSystems::MeshLifecycle),
+                .in_set(SteamAudioSystems::MeshLifecycle)
+                // Not present if the Steam Audio context could not be created
+                .run_if(resource_exists::<SteamAudioRootScene>),
         );
         app.add_observer(add_collider)
             .add_observer(remove_collider_of)
+            .add_observer(remove_sub_scene_user)
             .add_observer(add_sensor)
             .add_observer(add_not_steam_audio);
         app.init_resource::<ShapeToScene>();
@@ -124,10 +128,15 @@ fn add_collider(
     Ok(())
 }
 
+/// Sub-scenes are shared by all colliders with the same shape and material.
 #[derive(Resource, Default, Deref, DerefMut)]
-struct ShapeToScene(HashMap<ColliderKey, audionimbus::Scene>);
+struct ShapeToScene(HashMap<(ColliderKey, [u32; 7]), audionimbus::Scene>);
 
-#[derive(Deref, DerefMut)]
+/// The cached sub-scene an instanced collider uses, so that sub-scenes no longer in use can be dropped.
+#[derive(Component)]
+struct UsesSubScene((ColliderKey, [u32; 7]));
+
+#[derive(Clone, Deref, DerefMut)]
 struct ColliderKey(Weak<dyn Shape>);
 
 impl From<&Collider> for ColliderKey {
@@ -150,11 +159,12 @@ impl Hash for ColliderKey {
 }
 
 fn queue_steam_audio_mesh_processing(
-    colliders: Query<(Entity, Ref<Collider>), With<SteamAudioMaterial>>,
+    colliders: Query<(Entity, Ref<Collider>, Ref<SteamAudioMaterial>)>,
     mut commands: Commands,
 ) {
-    for (entity, mesh) in colliders.iter() {
-        if mesh.is_changed() {
+    for (entity, mesh, material) in colliders.iter() {
+        // Steam Audio cannot change the material of an existing mesh, so it is rebuilt instead
+        if mesh.is_changed() || material.is_changed() {
             commands
                 .entity(entity)
                 .try_insert(InSteamAudioMeshSpawnQueue);
@@ -168,7 +178,6 @@ fn spawn_new_steam_audio_meshes(
     queued: Query<
         (
             Entity,
-            NameOrEntity,
             &Collider,
             Option<&SteamAudioMaterial>,
             &GlobalTransform,
@@ -177,10 +186,8 @@ fn spawn_new_steam_audio_meshes(
         With<InSteamAudioMeshSpawnQueue>,
     >,
     mut root: ResMut<SteamAudioRootScene>,
-    mut errors: Local<Vec<String>>,
-) -> Result {
-    errors.clear();
-    for (entity, name, collider, material, transform, is_static) in &queued {
+) {
+    for (entity, collider, material, transform, is_static) in &queued {
         if material.is_none() {
             commands
                 .entity(entity)
@@ -188,7 +195,8 @@ fn spawn_new_steam_audio_meshes(
         }
         let material = material.copied().unwrap_or_default();
         if !is_static {
-            let sub_scene = if let Some(sub_scene) = map.get(&ColliderKey::from(collider)) {
+            let key = (ColliderKey::from(collider), material.to_bits());
+            let sub_scene = if let Some(sub_scene) = map.get(&key) {
                 sub_scene.clone()
             } else {
                 let mut sub_scene = match audionimbus::Scene::try_new(
@@ -197,9 +205,7 @@ fn spawn_new_steam_audio_meshes(
                 ) {
                     Ok(sub_scene) => sub_scene,
                     Err(err) => {
-                        errors.push(format!(
-                            "{name}: Failed to create sub-scene for mesh: {err}"
-                        ));
+                        commands.trigger(SteamAudioError::SubScene { entity, error: err });
                         commands
                             .entity(entity)
                             .try_remove::<InSteamAudioMeshSpawnQueue>();
@@ -209,7 +215,10 @@ fn spawn_new_steam_audio_meshes(
                 let mesh = match collider.trimesh_builder().build() {
                     Ok(mesh) => mesh,
                     Err(err) => {
-                        errors.push(format!("{name}: Failed to build mesh: {err}"));
+                        commands.trigger(SteamAudioError::MeshConversion {
+                            entity,
+                            error: err.into(),
+                        });
                         commands
                             .entity(entity)
                             .try_remove::<InSteamAudioMeshSpawnQueue>();
@@ -219,7 +228,7 @@ fn spawn_new_steam_audio_meshes(
                 let static_mesh = match mesh.to_steam_audio_mesh(&sub_scene, material.into()) {
                     Ok(mesh) => mesh,
                     Err(err) => {
-                        errors.push(format!("{name}: Failed to convert mesh: {err}"));
+                        commands.trigger(SteamAudioError::MeshConversion { entity, error: err });
                         commands
                             .entity(entity)
                             .try_remove::<InSteamAudioMeshSpawnQueue>();
@@ -229,7 +238,7 @@ fn spawn_new_steam_audio_meshes(
                 sub_scene.add_static_mesh(static_mesh);
                 // committing a new scene should be fine during simulation of a different scene
                 sub_scene.commit();
-                map.insert(collider.into(), sub_scene.clone());
+                map.insert(key.clone(), sub_scene.clone());
                 sub_scene
             };
             let transform = transform.to_steam_audio_transform();
@@ -242,7 +251,7 @@ fn spawn_new_steam_audio_meshes(
                 match audionimbus::InstancedMesh::try_new(&root, instanced_mesh_settings) {
                     Ok(instanced_mesh) => instanced_mesh,
                     Err(err) => {
-                        errors.push(format!("{name}: Failed to create instanced mesh: {err}"));
+                        commands.trigger(SteamAudioError::InstancedMesh { entity, error: err });
                         commands
                             .entity(entity)
                             .try_remove::<InSteamAudioMeshSpawnQueue>();
@@ -252,7 +261,8 @@ fn spawn_new_steam_audio_meshes(
             root.add_instanced_mesh(instanced_mesh.clone());
             commands
                 .entity(entity)
-                .try_insert(SteamAudioInstancedMesh(instanced_mesh));
+                .try_remove::<SteamAudioStaticMesh>()
+                .try_insert((SteamAudioInstancedMesh(instanced_mesh), UsesSubScene(key)));
         } else {
             let mesh = match collider
                 .trimesh_builder()
@@ -262,7 +272,10 @@ fn spawn_new_steam_audio_meshes(
             {
                 Ok(mesh) => mesh,
                 Err(err) => {
-                    errors.push(format!("{name}: Failed to build mesh: {err}"));
+                    commands.trigger(SteamAudioError::MeshConversion {
+                        entity,
+                        error: err.into(),
+                    });
                     commands
                         .entity(entity)
                         .try_remove::<InSteamAudioMeshSpawnQueue>();
@@ -272,7 +285,7 @@ fn spawn_new_steam_audio_meshes(
             let static_mesh = match mesh.to_steam_audio_mesh(&root, material.into()) {
                 Ok(mesh) => mesh,
                 Err(err) => {
-                    errors.push(format!("{name}: Failed to convert mesh: {err}"));
+                    commands.trigger(SteamAudioError::MeshConversion { entity, error: err });
                     commands
                         .entity(entity)
                         .try_remove::<InSteamAudioMeshSpawnQueue>();
@@ -282,6 +295,7 @@ fn spawn_new_steam_audio_meshes(
             root.add_static_mesh(static_mesh.clone());
             commands
                 .entity(entity)
+                .try_remove::<SteamAudioInstancedMesh>()
                 .try_insert(SteamAudioStaticMesh(static_mesh));
         }
 
@@ -294,7 +308,10 @@ fn spawn_new_steam_audio_meshes(
             let mesh = match collider.trimesh_builder().build() {
                 Ok(mesh) => mesh,
                 Err(err) => {
-                    errors.push(format!("{name}: Failed to convert mesh: {err}"));
+                    commands.trigger(SteamAudioError::MeshConversion {
+                        entity,
+                        error: err.into(),
+                    });
                     continue;
                 }
             };
@@ -307,16 +324,24 @@ fn spawn_new_steam_audio_meshes(
         continue;
     }
     // Do not call root.commit(), it's not safe while simulations are running
+}
 
-    if !errors.is_empty() {
-        Err(errors.join("\n").into())
-    } else {
-        Ok(())
-    }
+fn remove_sub_scene_user(remove: On<Remove, SteamAudioInstancedMesh>, mut commands: Commands) {
+    commands.entity(remove.entity).try_remove::<UsesSubScene>();
 }
 
-fn garbage_collect_meshes(mut map: ResMut<ShapeToScene>) {
-    map.retain(|shape, _| Weak::strong_count(shape) > 0);
+fn garbage_collect_meshes(
+    mut map: ResMut<ShapeToScene>,
+    users: Query<&UsesSubScene>,
+    changed_users: Query<(), Changed<UsesSubScene>>,
+    mut removed_users: RemovedComponents<UsesSubScene>,
+) {
+    map.retain(|(shape, _), _| Weak::strong_count(shape) > 0);
+    // Otherwise, animating a material would leave behind a sub-scene for every value it went through
+    if removed_users.read().count() > 0 || !changed_users.is_empty() {
+        let used = users.iter().map(|user| &user.0).collect::<HashSet<_>>();
+        map.retain(|key, _| used.contains(key));
+    }
 }
 
 pub trait ToSteamAudioMesh {
@@ -324,7 +349,7 @@ pub trait ToSteamAudioMesh {
         &self,
         scene: &audionimbus::Scene,
         material: audionimbus::Material,
-    ) -> Result<audionimbus::StaticMesh>;
+    ) -> Result<audionimbus::StaticMesh, MeshConversionError>;
 }
 
 impl ToSteamAudioMesh for Trimesh {
@@ -332,7 +357,7 @@ impl ToSteamAudioMesh for Trimesh {
         &self,
         scene: &audionimbus::Scene,
         material: audionimbus::Material,
-    ) -> Result<audionimbus::StaticMesh> {
+    ) -> Result<audionimbus::StaticMesh, MeshConversionError> {
         let vertices = self
             .vertices
             .iter()
@@ -11,6 +11,7 @@ use avian3d::parry::shape::{SharedShape, TypedShape};
 use avian3d::prelude::*;
 use bevy_log::warn;
 use bevy_mesh::prelude::*;
+use bevy_steam_audio::error::MeshConversionError;
 use bevy_utils::default;
 use thiserror::Error;
 
@@ -106,6 +107,14 @@ pub(crate) enum TrimeshBuilderError {
     UnsupportedShape(String),
 }
 
+impl From<TrimeshBuilderError> for MeshConversionError {
+    fn from(err: TrimeshBuilderError) -> Self {
+        match err {
+            TrimeshBuilderError::UnsupportedShape(shape) => Self::UnsupportedShape(shape),
+        }
+    }
+}
+
 impl TrimeshBuilder {
     /// Creates a new [`TrimeshBuilder`] for the given shape. Usually you'll want to call [`Collider::trimesh_builder`] instead.
     pub(crate) fn new(shape: SharedShape) -> Self {
@@ -0,0 +1,100 @@
+use core::ffi::{CStr, c_char};
+use std::sync::OnceLock;
+
+use crate::prelude::*;
+
+/// The Steam Audio context shared by everything in this crate.
+///
+/// This is created by [`SteamAudioPlugin`] using its [`SteamAudioContextSettings`].
+pub static STEAM_AUDIO_CONTEXT: SteamAudioContext = SteamAudioContext(OnceLock::new());
+
+/// Holds the global [`audionimbus::Context`].
+///
+/// Dereferencing this before [`SteamAudioPlugin`] successfully created the context panics.
+/// Use [`SteamAudioContext::get`] if you need to check first.
+pub struct SteamAudioContext(OnceLock<audionimbus::Context>);
+
+impl SteamAudioContext {
+    /// Returns the context, or `None` if it has not been created (yet).
+    pub fn get(&self) -> Option<&audionimbus::Context> {
+        self.0.get()
+    }
+
+    /// Creates the context if it does not exist yet.
+    /// Since there is only one context per process, the settings are ignored if it already exists.
+    pub(crate) fn init(
+        &self,
+        settings: &SteamAudioContextSettings,
+    ) -> Result<&audionimbus::Context, audionimbus::SteamAudioError> {
+        if let Some(context) = self.0.get() {
+            return Ok(context);
+        }
+        let context = audionimbus::Context::try_new(&settings.to_audionimbus())?;
+        Ok(self.0.get_or_init(|| context))
+    }
+}
+
+impl core::ops::Deref for SteamAudioContext {
+    type Target = audionimbus::Context;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.get().expect(
+            "Used the Steam Audio context before it was created. Did you add `SteamAudioPlugin`? If so, check the logs for why context creation failed.",
+        )
+    }
+}
+
+/// Settings used to create the Steam Audio context.
+#[derive(Debug, Clone, Copy)]
+pub struct SteamAudioContextSettings {
+    /// The maximum SIMD instruction set level that Steam Audio should use.
+    ///
+    /// Steam Audio automatically chooses the best instruction set supported by the CPU.
+    /// Lower this if e.g. AVX512 throttles the CPU clock speed on your target hardware.
+    pub simd_level: audionimbus::SimdLevel,
+
+    /// If `true`, all Steam Audio API calls perform extra validation checks and log what they find.
+    /// This imposes a significant performance penalty, so it defaults to `cfg!(debug_assertions)`:
+    /// debug builds catch API misuse early, while release builds skip the checks.
+    pub validation: bool,
+}
+
+impl Default for SteamAudioContextSettings {
+    fn default() -> Self {
+        Self {
+            simd_level: audionimbus::SimdLevel::default(),
+            validation: cfg!(debug_assertions),
+        }
+    }
+}
+
+impl SteamAudioContextSettings {
+    fn to_audionimbus(self) -> audionimbus::ContextSettings {
+        let mut flags = audionimbus::ContextFlags::empty();
+        if self.validation {
+            flags |= audionimbus::ContextFlags::VALIDATION;
+        }
+        audionimbus::ContextSettings {
+            log_callback: Some(log_callback),
+            simd_level: self.simd_level,
+            flags,
+            ..default()
+        }
+    }
+}
+
+/// Forwards Steam Audio's log messages to `bevy_log`.
+unsafe extern "C" fn log_callback(level: audionimbus_sys::IPLLogLevel, message: *const c_char) {
+    if message.is_null() {
+        return;
+    }
+    // SAFETY: Steam Audio passes a valid nul-terminated string that lives for the duration of this call.
+    let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+    let message = message.trim_end();
+    match level {
+        audionimbus_sys::IPLLogLevel::IPL_LOGLEVEL_INFO => info!("Steam Audio: {message}"),
+        audionimbus_sys::IPLLogLevel::IPL_LOGLEVEL_WARNING => warn!("Steam Audio: {message}"),
+        audionimbus_sys::IPLLogLevel::IPL_LOGLEVEL_ERROR => error!("Steam Audio: {message}"),
+        audionimbus_sys::IPLLogLevel::IPL_LOGLEVEL_DEBUG => debug!("Steam Audio: {message}"),
+    }
+}
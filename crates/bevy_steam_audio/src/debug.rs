@@ -2,14 +2,20 @@ use bevy_color::{Color, palettes::tailwind};
 use bevy_ecs::entity_disabling::Disabled;
 use thiserror::Error;
 
-use crate::prelude::*;
+use crate::{
+    prelude::*,
+    wrapper::{MeshTriangles, is_degenerate, weld_vertices},
+};
 
 #[derive(Default)]
 pub struct SteamAudioDebugPlugin;
 
 impl Plugin for SteamAudioDebugPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PostUpdate, update_gizmos.in_set(SteamAudioSystems::Gizmos));
+        app.add_systems(
+            SteamAudioSchedule::from_app(app),
+            update_gizmos.in_set(SteamAudioSystems::Gizmos),
+        );
         app.add_observer(remove_gizmo);
         app.insert_gizmo_config(
             SteamAudioGizmos,
@@ -38,16 +44,14 @@ pub struct SteamAudioGizmo {
 pub enum SteamAudioGizmoError {
     #[error("Mesh has no positions")]
     NoPositions,
-    #[error("Mesh has no indices")]
-    NoIndices,
+    #[error("Mesh is not made of triangles")]
+    NotTriangles,
 }
 
 impl TryFrom<&Mesh> for SteamAudioGizmo {
     type Error = SteamAudioGizmoError;
 
     fn try_from(mesh: &Mesh) -> Result<Self, Self::Error> {
-        use itertools::Itertools as _;
-
         let Some(vertices) = mesh
             .attribute(Mesh::ATTRIBUTE_POSITION)
             .and_then(|p| p.as_float3())
@@ -55,23 +59,14 @@ impl TryFrom<&Mesh> for SteamAudioGizmo {
             return Err(SteamAudioGizmoError::NoPositions);
         };
 
-        let Some(indices) = mesh.indices() else {
-            return Err(SteamAudioGizmoError::NoIndices);
+        let Ok(MeshTriangles { mut triangles, .. }) = MeshTriangles::try_from(mesh) else {
+            return Err(SteamAudioGizmoError::NotTriangles);
         };
+        let vertices = weld_vertices(vertices, &mut triangles);
+        triangles.retain(|triangle| !is_degenerate(triangle));
         let gizmo = SteamAudioGizmo {
-            vertices: vertices.iter().map(|v| Vec3::from_array(*v)).collect(),
-            indices: indices
-                .iter()
-                .chunks(3)
-                .into_iter()
-                .map(|mut chunk| {
-                    [
-                        chunk.next().unwrap() as u32,
-                        chunk.next().unwrap() as u32,
-                        chunk.next().unwrap() as u32,
-                    ]
-                })
-                .collect(),
+            vertices: vertices.into_iter().map(Vec3::from_array).collect(),
+            indices: triangles,
         };
 
         Ok(gizmo)
@@ -0,0 +1,245 @@
+use std::sync::LazyLock;
+
+use bevy_ecs::entity_disabling::Disabled;
+use bevy_seedling::{pool::sample_effects::EffectsQueryError, prelude::FirewheelNode};
+use crossbeam_channel::{Receiver, Sender};
+use firewheel::node::NodeID;
+
+use crate::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    let schedule = SteamAudioSchedule::from_app(app);
+    app.add_systems(
+        schedule,
+        forward_processor_errors.in_set(SteamAudioSystems::UpdateSources),
+    );
+    app.add_observer(log_steam_audio_error);
+}
+
+/// An error that occurred inside of Steam Audio.
+///
+/// These are triggered as events, so you can observe them to react to failures yourself.
+/// By default, they are also logged.
+#[derive(Event, Debug, thiserror::Error)]
+pub enum SteamAudioError {
+    /// An audio node failed to create its Steam Audio effects.
+    /// The node falls back to passing its input through unchanged or to silence, depending on the node,
+    /// and is rebuilt the next time [`SteamAudioReady`](crate::simulation::SteamAudioReady) is triggered.
+    /// If the effects of a running node could not be replaced, e.g. after a stream restart, it keeps its current ones.
+    #[error("{entity}: Failed to create the effects of a {node}: {error}")]
+    Processor {
+        /// The entity holding the node, or [`Entity::PLACEHOLDER`] if it was already despawned.
+        entity: Entity,
+        /// The name of the node.
+        node: &'static str,
+        /// The underlying error.
+        error: audionimbus::SteamAudioError,
+    },
+    /// A mesh or collider could not be converted into a Steam Audio mesh.
+    /// The entity is not part of the acoustic scene.
+    #[error("{entity}: Failed to convert mesh: {error}")]
+    MeshConversion {
+        /// The entity holding the mesh or collider.
+        entity: Entity,
+        /// The underlying error.
+        error: MeshConversionError,
+    },
+    /// The sub-scene used to instance a non-[`Static`](crate::scene::Static) mesh could not be created.
+    #[error("{entity}: Failed to create sub-scene for mesh: {error}")]
+    SubScene {
+        /// The entity holding the mesh or collider.
+        entity: Entity,
+        /// The underlying error.
+        error: audionimbus::SteamAudioError,
+    },
+    /// A non-[`Static`](crate::scene::Static) mesh could not be instanced into the root scene.
+    #[error("{entity}: Failed to create instanced mesh: {error}")]
+    InstancedMesh {
+        /// The entity holding the mesh or collider.
+        entity: Entity,
+        /// The underlying error.
+        error: audionimbus::SteamAudioError,
+    },
+    /// The debug gizmo of a mesh could not be created.
+    #[cfg(feature = "debug")]
+    #[error("{entity}: Failed to create gizmo: {error}")]
+    Gizmo {
+        /// The entity holding the mesh.
+        entity: Entity,
+        /// The underlying error.
+        error: crate::debug::SteamAudioGizmoError,
+    },
+    /// The Steam Audio source of a sample player could not be created.
+    /// The sample plays without simulation.
+    #[error("{entity}: Failed to create Steam Audio source: {error}")]
+    SourceCreation {
+        /// The sample player.
+        entity: Entity,
+        /// The underlying error.
+        error: audionimbus::SteamAudioError,
+    },
+    /// A sample player with a Steam Audio source does not have exactly one [`SteamAudioNode`] effect.
+    #[error("{entity}: Failed to get Steam Audio node from source: {error}")]
+    SourceNode {
+        /// The sample player.
+        entity: Entity,
+        /// The underlying error.
+        error: EffectsQueryError,
+    },
+    /// The Steam Audio context could not be created.
+    /// Spatial audio is unavailable for the lifetime of the app, see [`SteamAudioUnavailable`](crate::SteamAudioUnavailable).
+    #[error("Failed to create Steam Audio context, spatial audio will be unavailable: {error}")]
+    Context {
+        /// The underlying error.
+        error: audionimbus::SteamAudioError,
+    },
+    /// The simulator could not be created.
+    /// Spatial audio stays unavailable until it is successfully recreated, e.g. by changing [`SteamAudioQuality`].
+    #[error("Failed to create simulator: {error}")]
+    Simulator {
+        /// The underlying error.
+        error: audionimbus::SteamAudioError,
+    },
+    /// The dedicated [`SteamAudioSimulationThread`](crate::settings::SteamAudioSimulationThread) could not be spawned.
+    /// Reflections and pathing are simulated synchronously on the main thread instead.
+    #[error("Failed to spawn simulation thread, simulating on the main thread instead: {error}")]
+    SimulationThread {
+        /// The underlying error.
+        error: std::io::Error,
+    },
+    /// The Steam Audio extras of a glTF node or material could not be read.
+    /// The mesh keeps the acoustic properties it had before, if any.
+    #[cfg(feature = "gltf")]
+    #[error("{entity}: Failed to read glTF extras: {error}")]
+    GltfExtras {
+        /// The entity holding the mesh.
+        entity: Entity,
+        /// The underlying error.
+        error: GltfExtrasError,
+    },
+    /// Probes could not be generated or baked.
+    #[error("Failed to bake probes: {error}")]
+    Baking {
+        /// The underlying error.
+        error: audionimbus::SteamAudioError,
+    },
+}
+
+impl SteamAudioError {
+    /// The entity this error is about, if it is about a specific one.
+    pub fn entity(&self) -> Option<Entity> {
+        match self {
+            Self::Processor { entity, .. }
+            | Self::MeshConversion { entity, .. }
+            | Self::SubScene { entity, .. }
+            | Self::InstancedMesh { entity, .. }
+            | Self::SourceCreation { entity, .. }
+            | Self::SourceNode { entity, .. } => Some(*entity),
+            #[cfg(feature = "debug")]
+            Self::Gizmo { entity, .. } => Some(*entity),
+            #[cfg(feature = "gltf")]
+            Self::GltfExtras { entity, .. } => Some(*entity),
+            Self::Context { .. }
+            | Self::Simulator { .. }
+            | Self::SimulationThread { .. }
+            | Self::Baking { .. } => None,
+        }
+    }
+}
+
+/// The reason a mesh could not be converted into a Steam Audio mesh.
+#[derive(Debug, thiserror::Error)]
+pub enum MeshConversionError {
+    /// Only [`PrimitiveTopology::TriangleList`](bevy_mesh::PrimitiveTopology::TriangleList)
+    /// and [`PrimitiveTopology::TriangleStrip`](bevy_mesh::PrimitiveTopology::TriangleStrip) are supported.
+    #[error("Mesh is not made of triangles")]
+    NotTriangles,
+    /// The mesh has no [`Mesh::ATTRIBUTE_POSITION`].
+    #[error("Mesh has no position attribute")]
+    NoPositions,
+    /// The [`Mesh::ATTRIBUTE_POSITION`] is not made of `[f32; 3]`.
+    #[error("Mesh position attribute is not a float3")]
+    PositionsNotFloat3,
+    /// The mesh asset is not available.
+    #[error("Mesh asset is not loaded")]
+    MeshNotLoaded,
+    /// The material has no asset path that could be mapped to a [`SteamAudioMaterial`].
+    #[error("Material has no asset path")]
+    MaterialWithoutPath,
+    /// The shape of a collider cannot be turned into a triangle mesh.
+    #[error("Unsupported shape: {0}")]
+    UnsupportedShape(String),
+    /// Steam Audio rejected the mesh.
+    #[error(transparent)]
+    SteamAudio(#[from] audionimbus::SteamAudioError),
+}
+
+/// The reason the Steam Audio extras of a glTF node or material could not be read.
+#[cfg(feature = "gltf")]
+#[derive(Debug, thiserror::Error)]
+pub enum GltfExtrasError {
+    /// The extras are not valid JSON, or the Steam Audio block does not have the expected shape.
+    #[error("Invalid extras: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The preset does not name one of the [`SteamAudioMaterial`] constants.
+    #[error("Unknown material preset: {0}")]
+    UnknownPreset(String),
+}
+
+/// The reason a [`SteamAudioMaterialAsset`](crate::scene::material_asset::SteamAudioMaterialAsset) could not be loaded.
+#[cfg(feature = "material_asset")]
+#[derive(Debug, thiserror::Error)]
+pub enum MaterialAssetError {
+    /// The file could not be read.
+    #[error("Failed to read material: {0}")]
+    Io(#[from] std::io::Error),
+    /// The file is not a valid RON material.
+    #[error("Failed to parse material: {0}")]
+    Ron(#[from] ron::de::SpannedError),
+}
+
+fn log_steam_audio_error(error: On<SteamAudioError>) {
+    error!("{}", *error);
+}
+
+struct ProcessorError {
+    node_id: NodeID,
+    node: &'static str,
+    error: audionimbus::SteamAudioError,
+}
+
+/// Processors have no access to the ECS, so they send their errors through here.
+static PROCESSOR_ERRORS: LazyLock<(Sender<ProcessorError>, Receiver<ProcessorError>)> =
+    LazyLock::new(crossbeam_channel::unbounded);
+
+/// Reports a failure of a processor to the ECS.
+/// Only call this on failure, as sending may allocate.
+pub(crate) fn report_processor_error(
+    node_id: NodeID,
+    node: &'static str,
+    error: audionimbus::SteamAudioError,
+) {
+    // The receiver lives in a static, so this cannot fail.
+    let _ = PROCESSOR_ERRORS.0.send(ProcessorError {
+        node_id,
+        node,
+        error,
+    });
+}
+
+fn forward_processor_errors(
+    nodes: Query<(Entity, &FirewheelNode), Allow<Disabled>>,
+    mut commands: Commands,
+) {
+    for error in PROCESSOR_ERRORS.1.try_iter() {
+        let entity = nodes
+            .iter()
+            .find_map(|(entity, node)| (node.0 == error.node_id).then_some(entity))
+            .unwrap_or(Entity::PLACEHOLDER);
+        commands.trigger(SteamAudioError::Processor {
+            entity,
+            node: error.node,
+            error: error.error,
+        });
+    }
+}
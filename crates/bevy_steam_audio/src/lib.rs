@@ -1,15 +1,18 @@
-use std::marker::PhantomData;
-use std::sync::LazyLock;
-
+use bevy_ecs::schedule::{InternedScheduleLabel, InternedSystemSet, ScheduleLabel};
 use prelude::*;
 
+pub mod context;
+pub mod error;
+pub mod models;
 pub mod nodes;
+mod pause;
 pub mod probes;
 pub mod scene;
 pub mod simulation;
 pub mod sources;
 pub mod wrapper;
 pub use audionimbus;
+pub use context::STEAM_AUDIO_CONTEXT;
 #[cfg(feature = "debug")]
 pub mod debug;
 pub mod settings;
@@ -17,7 +20,7 @@ pub mod settings;
 pub mod prelude {
     #[cfg(feature = "debug")]
     pub(crate) use crate::debug::SteamAudioGizmo;
-    pub(crate) use crate::{STEAM_AUDIO_CONTEXT, SteamAudioSystems};
+    pub(crate) use crate::{STEAM_AUDIO_CONTEXT, SteamAudioSchedule, SteamAudioSystems};
     pub(crate) use bevy_app::prelude::*;
     pub(crate) use bevy_asset::prelude::*;
     pub(crate) use bevy_derive::{Deref, DerefMut};
@@ -36,51 +39,120 @@ pub mod prelude {
 
     #[cfg(feature = "debug")]
     pub use crate::debug::SteamAudioDebugPlugin;
+    #[cfg(feature = "material_asset")]
+    pub use crate::scene::material_asset::{SteamAudioMaterialAsset, SteamAudioMaterialHandle};
+    #[cfg(feature = "material_mapping")]
+    pub use crate::scene::material_mapping::SteamAudioMaterialMapping;
     pub use crate::{
-        SteamAudioListener, SteamAudioPlugin,
+        SteamAudioListener, SteamAudioPlugin, SteamAudioUnavailable,
+        context::SteamAudioContextSettings,
+        error::SteamAudioError,
+        models::{SteamAudioAirAbsorption, SteamAudioDistanceAttenuation},
         nodes::{
-            AmbisonicDecodeNode, SteamAudioNode, SteamAudioPool, SteamAudioReverbNode,
-            SteamAudioReverbPool,
+            AmbisonicDecodeNode, SteamAudioDefaultPools, SteamAudioNode, SteamAudioPool,
+            SteamAudioReverbNode, SteamAudioReverbPool,
         },
         probes::GenerateProbes,
-        scene::Static,
+        scene::{NotSteamAudioMesh, Static},
         settings::{
-            SteamAudioDirectQuality, SteamAudioPathingQuality, SteamAudioQuality,
-            SteamAudioReflectionsQuality,
+            SteamAudioDirectQuality, SteamAudioLatencyMode, SteamAudioOutputMode,
+            SteamAudioPathingQuality, SteamAudioPauseMode, SteamAudioQuality,
+            SteamAudioQualityPreset, SteamAudioReflectionsQuality, SteamAudioSimulationBudget,
+            SteamAudioSimulationThread, SteamAudioThreadPriority,
+        },
+        simulation::{SteamAudioSimulationStats, SteamAudioSourcePool},
+        sources::{SteamAudioHrtfInterpolation, SteamAudioSource},
+        wrapper::{
+            ATTRIBUTE_STEAM_AUDIO_MATERIAL, SteamAudioMaterial, SteamAudioMaterialRange,
+            SteamAudioMaterials,
         },
-        wrapper::SteamAudioMaterial,
     };
 }
 
+/// Adds Steam Audio to the app.
 pub struct SteamAudioPlugin {
-    _pd: PhantomData<()>,
+    /// Settings used to create the Steam Audio context.
+    pub context: SteamAudioContextSettings,
+    /// The pools spawned on startup.
+    /// Set this to `None` if you set up your own audio graph, e.g. with custom pools.
+    pub default_pools: Option<SteamAudioDefaultPools>,
+    /// The schedule all Steam Audio systems run in.
+    /// Plugins building on top of this one, such as scene backends, should be added after it so they pick this up.
+    pub schedule: InternedScheduleLabel,
+    /// If set, all [`SteamAudioSystems`] are nested in this set, so they can be ordered relative to your own systems as a whole.
+    pub system_set: Option<InternedSystemSet>,
+    /// The initial [`SteamAudioQuality`].
+    pub quality: SteamAudioQuality,
+    /// The initial [`SteamAudioOutputMode`].
+    pub output_mode: SteamAudioOutputMode,
+    /// The initial [`SteamAudioSimulationThread`].
+    pub simulation_thread: SteamAudioSimulationThread,
+    /// The initial [`SteamAudioLatencyMode`].
+    pub latency_mode: SteamAudioLatencyMode,
 }
 
 impl Default for SteamAudioPlugin {
     fn default() -> Self {
-        Self { _pd: PhantomData }
+        Self {
+            context: SteamAudioContextSettings::default(),
+            default_pools: Some(SteamAudioDefaultPools::default()),
+            schedule: PostUpdate.intern(),
+            system_set: None,
+            quality: SteamAudioQuality::default(),
+            output_mode: SteamAudioOutputMode::default(),
+            simulation_thread: SteamAudioSimulationThread::default(),
+            latency_mode: SteamAudioLatencyMode::default(),
+        }
     }
 }
 
 impl Plugin for SteamAudioPlugin {
     fn build(&self, app: &mut App) {
+        app.insert_resource(SteamAudioSchedule(self.schedule));
+        let sets = (
+            SteamAudioSystems::CreateSimulator,
+            SteamAudioSystems::MeshLifecycle,
+            SteamAudioSystems::UpdateTransforms,
+            SteamAudioSystems::UpdateSources,
+            SteamAudioSystems::RunSimulator,
+            SteamAudioSystems::GenerateProbes,
+            SteamAudioSystems::Gizmos,
+        );
+        if let Some(system_set) = self.system_set {
+            app.configure_sets(self.schedule, sets.clone().in_set(system_set));
+        }
         app.configure_sets(
-            PostUpdate,
-            (
-                SteamAudioSystems::CreateSimulator,
-                SteamAudioSystems::MeshLifecycle,
-                SteamAudioSystems::UpdateTransforms,
-                SteamAudioSystems::UpdateSources,
-                SteamAudioSystems::RunSimulator,
-                SteamAudioSystems::GenerateProbes,
-                SteamAudioSystems::Gizmos,
-            )
-                .chain()
-                .after(TransformSystems::Propagate),
+            self.schedule,
+            sets.chain().after(TransformSystems::Propagate),
         );
+        // Nodes read these when they are spawned, even if Steam Audio is unavailable
+        app.insert_resource(self.quality)
+            .insert_resource(self.output_mode)
+            .insert_resource(self.simulation_thread)
+            .insert_resource(self.latency_mode);
+        app.add_plugins(error::plugin);
+        if let Err(error) = STEAM_AUDIO_CONTEXT.init(&self.context) {
+            // Reported on startup so observers added after this plugin see it too
+            let mut error = Some(error);
+            app.insert_resource(SteamAudioUnavailable).add_systems(
+                PreStartup,
+                move |mut commands: Commands| {
+                    if let Some(error) = error.take() {
+                        commands.trigger(SteamAudioError::Context { error });
+                    }
+                },
+            );
+            return;
+        }
+        if let Some(default_pools) = self.default_pools.clone() {
+            app.insert_resource(default_pools)
+                .add_systems(PreStartup, nodes::setup_nodes);
+        }
         app.add_plugins((
             nodes::plugin,
+            models::plugin,
             simulation::plugin,
+            pause::plugin,
             wrapper::plugin,
             scene::plugin,
             settings::plugin,
@@ -90,6 +162,24 @@ impl Plugin for SteamAudioPlugin {
     }
 }
 
+/// Inserted if the Steam Audio context could not be created.
+/// No Steam Audio systems run then, and the Steam Audio nodes are not registered with the audio graph.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct SteamAudioUnavailable;
+
+/// The schedule the Steam Audio systems run in, as configured in [`SteamAudioPlugin::schedule`].
+#[derive(Resource, Debug, Clone, Copy, Deref)]
+pub struct SteamAudioSchedule(pub InternedScheduleLabel);
+
+impl SteamAudioSchedule {
+    /// Returns the configured schedule, or [`PostUpdate`] if [`SteamAudioPlugin`] has not been added (yet).
+    pub fn from_app(app: &App) -> InternedScheduleLabel {
+        app.world()
+            .get_resource::<SteamAudioSchedule>()
+            .map_or_else(|| PostUpdate.intern(), |schedule| schedule.0)
+    }
+}
+
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SteamAudioSystems {
     CreateSimulator,
@@ -105,7 +195,3 @@ pub enum SteamAudioSystems {
 #[reflect(Component)]
 #[require(Transform, GlobalTransform)]
 pub struct SteamAudioListener;
-
-pub static STEAM_AUDIO_CONTEXT: LazyLock<audionimbus::Context> = LazyLock::new(|| {
-    audionimbus::Context::try_new(&audionimbus::ContextSettings::default()).unwrap()
-});
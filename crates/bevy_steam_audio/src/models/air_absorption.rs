@@ -0,0 +1,172 @@
+use core::ffi::c_void;
+use std::sync::Arc;
+
+use firewheel::{
+    collector::ArcGc,
+    diff::{Diff, Patch, RealtimeClone},
+};
+
+use crate::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SteamAudioAirAbsorption>();
+}
+
+/// How much sound is absorbed by the medium it travels through, per frequency band.
+///
+/// As a resource, this is the model used by all sources.
+/// Insert this as a component on a [`SamplePlayer`](bevy_seedling::prelude::SamplePlayer) to override the model for that source.
+/// The same model is used for both the direct path simulation and the rendering in the [`SteamAudioNode`].
+#[derive(
+    Resource, Component, Debug, Clone, Default, PartialEq, Reflect, Diff, Patch, RealtimeClone,
+)]
+#[reflect(Resource, Component, Default, Debug, Clone, PartialEq)]
+pub enum SteamAudioAirAbsorption {
+    /// Steam Audio's default model.
+    /// This is an exponential falloff, with decay rates derived from physical properties of air.
+    #[default]
+    Default,
+
+    /// An exponential falloff.
+    Exponential {
+        /// The decay rates per meter for the low, middle and high frequency bands.
+        coefficients: [f32; 3],
+    },
+
+    /// A user-defined function mapping the distance in meters and the frequency band
+    /// to an air absorption between 0.0 and 1.0.
+    Callback(SteamAudioAirAbsorptionCallback),
+}
+
+impl SteamAudioAirAbsorption {
+    /// Dry air, which lets high frequencies die out a bit faster than [`SteamAudioAirAbsorption::Default`].
+    pub const DRY_AIR: Self = Self::Exponential {
+        coefficients: [0.0002, 0.0025, 0.0300],
+    };
+
+    /// Thick, humid fog, which muffles distant sounds noticeably.
+    ///
+    /// Note that this is tuned to sound right, not to be physically accurate.
+    pub const HUMID_FOG: Self = Self::Exponential {
+        coefficients: [0.0010, 0.0080, 0.0500],
+    };
+
+    /// Heavy damping of everything but the lowest frequencies, similar to hearing sounds underwater.
+    ///
+    /// Note that this is tuned to sound right, not to be physically accurate.
+    pub const UNDERWATER: Self = Self::Exponential {
+        coefficients: [0.0050, 0.0500, 0.2500],
+    };
+
+    /// Creates an air absorption model from an arbitrary function.
+    /// The function receives the distance in meters and the band index (0 = low, 1 = middle, 2 = high frequencies),
+    /// and returns the air absorption between 0.0 (not audible) and 1.0 (not attenuated).
+    pub fn callback(callback: impl Fn(f32, usize) -> f32 + Send + Sync + 'static) -> Self {
+        Self::Callback(SteamAudioAirAbsorptionCallback::new(callback))
+    }
+
+    /// Returns the air absorption for the low, middle and high frequency bands
+    /// to apply to a source that is `distance` meters away from the listener.
+    pub fn air_absorption(&self, distance: f32) -> [f32; 3] {
+        match self {
+            Self::Default => {
+                steam_audio_air_absorption(&audionimbus::AirAbsorptionModel::Default, distance)
+            }
+            Self::Exponential { coefficients } => steam_audio_air_absorption(
+                &audionimbus::AirAbsorptionModel::Exponential {
+                    coefficients: *coefficients,
+                },
+                distance,
+            ),
+            Self::Callback(callback) => {
+                core::array::from_fn(|band| (callback.0)(distance, band).clamp(0.0, 1.0))
+            }
+        }
+    }
+}
+
+fn steam_audio_air_absorption(model: &audionimbus::AirAbsorptionModel, distance: f32) -> [f32; 3] {
+    audionimbus::air_absorption(
+        &STEAM_AUDIO_CONTEXT,
+        &audionimbus::Vector3::new(distance, 0.0, 0.0),
+        &audionimbus::Vector3::new(0.0, 0.0, 0.0),
+        model,
+    )
+    .0
+}
+
+/// A [`SteamAudioAirAbsorption`] shared with Steam Audio, which evaluates [`SteamAudioAirAbsorption::Callback`] through a callback.
+///
+/// The simulator may call into the model until the source using it is removed and the simulator is committed,
+/// so this must be kept alive until then.
+#[derive(Component, Clone, PartialEq)]
+pub(crate) struct SourceAirAbsorptionModel(pub(crate) ArcGc<SteamAudioAirAbsorption>);
+
+impl Default for SourceAirAbsorptionModel {
+    fn default() -> Self {
+        Self::new(SteamAudioAirAbsorption::Default)
+    }
+}
+
+impl SourceAirAbsorptionModel {
+    pub(crate) fn new(model: SteamAudioAirAbsorption) -> Self {
+        Self(ArcGc::new(model))
+    }
+
+    /// Converts this into a model Steam Audio can use for simulation.
+    /// Set `dirty` if the model changed since the last simulation.
+    pub(crate) fn to_audionimbus(&self, dirty: bool) -> audionimbus::AirAbsorptionModel {
+        match &*self.0 {
+            SteamAudioAirAbsorption::Default => audionimbus::AirAbsorptionModel::Default,
+            SteamAudioAirAbsorption::Exponential { coefficients } => {
+                audionimbus::AirAbsorptionModel::Exponential {
+                    coefficients: *coefficients,
+                }
+            }
+            SteamAudioAirAbsorption::Callback(callback) => {
+                audionimbus::AirAbsorptionModel::Callback {
+                    callback: air_absorption_callback,
+                    user_data: core::ptr::from_ref(callback).cast_mut().cast(),
+                    dirty,
+                }
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn air_absorption_callback(
+    distance: f32,
+    band: i32,
+    user_data: *mut c_void,
+) -> f32 {
+    // SAFETY: `user_data` points into a `SourceAirAbsorptionModel`,
+    // which is kept alive for as long as the simulator may call this.
+    let callback = unsafe {
+        &*user_data
+            .cast_const()
+            .cast::<SteamAudioAirAbsorptionCallback>()
+    };
+    usize::try_from(band).map_or(1.0, |band| (callback.0)(distance, band).clamp(0.0, 1.0))
+}
+
+/// A function mapping the distance in meters and the frequency band to an air absorption between 0.0 and 1.0.
+///
+/// The function is shared with the audio thread, so cloning this is cheap.
+#[derive(Clone, PartialEq, RealtimeClone, Reflect)]
+#[reflect(opaque, Debug, Clone, PartialEq)]
+pub struct SteamAudioAirAbsorptionCallback(ArcGc<dyn Fn(f32, usize) -> f32 + Send + Sync>);
+
+impl SteamAudioAirAbsorptionCallback {
+    pub fn new(callback: impl Fn(f32, usize) -> f32 + Send + Sync + 'static) -> Self {
+        Self(ArcGc::new_unsized(|| {
+            Arc::new(callback) as Arc<dyn Fn(f32, usize) -> f32 + Send + Sync>
+        }))
+    }
+}
+
+impl core::fmt::Debug for SteamAudioAirAbsorptionCallback {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("SteamAudioAirAbsorptionCallback")
+            .finish_non_exhaustive()
+    }
+}
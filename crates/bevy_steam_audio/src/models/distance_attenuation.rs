@@ -0,0 +1,174 @@
+use core::ffi::c_void;
+use std::sync::Arc;
+
+use bevy_math::curve::{Curve, UnevenSampleAutoCurve, cores::UnevenCoreError};
+use firewheel::{
+    collector::ArcGc,
+    diff::{Diff, Patch, RealtimeClone},
+};
+
+use crate::prelude::*;
+
+/// How the volume of a source falls off with its distance to the listener.
+///
+/// Insert this on a [`SamplePlayer`](bevy_seedling::prelude::SamplePlayer) to override the attenuation of that source.
+/// Sources without this component use [`SteamAudioDistanceAttenuation::Default`].
+/// The same model is used for both the direct path simulation and the rendering in the [`SteamAudioNode`].
+#[derive(Component, Debug, Clone, Default, PartialEq, Reflect, Diff, Patch, RealtimeClone)]
+#[reflect(Component, Default, Debug, Clone, PartialEq)]
+pub enum SteamAudioDistanceAttenuation {
+    /// Steam Audio's default model.
+    /// This is an inverse distance falloff, with all sounds within 1 meter of the listener rendered without distance attenuation.
+    #[default]
+    Default,
+
+    /// An inverse distance falloff.
+    InverseDistance {
+        /// No distance attenuation is applied to any sound whose distance from the listener is less than this value.
+        min_distance: f32,
+        /// If set, the falloff is rescaled so that it reaches silence at exactly this distance.
+        /// Sounds further away than this are not audible at all.
+        max_distance: Option<f32>,
+    },
+
+    /// A user-authored curve mapping the distance in meters to an attenuation between 0.0 and 1.0.
+    Curve(SteamAudioAttenuationCurve),
+}
+
+impl SteamAudioDistanceAttenuation {
+    /// Creates an inverse distance falloff that is silent beyond `max_distance`.
+    pub fn inverse_distance(min_distance: f32, max_distance: f32) -> Self {
+        Self::InverseDistance {
+            min_distance,
+            max_distance: Some(max_distance),
+        }
+    }
+
+    /// Creates an attenuation from an arbitrary curve.
+    /// Distances outside of the curve's domain are clamped to it.
+    pub fn curve(curve: impl Curve<f32> + Send + Sync + 'static) -> Self {
+        Self::Curve(SteamAudioAttenuationCurve::new(curve))
+    }
+
+    /// Creates an attenuation curve from `(distance, attenuation)` keyframes, linearly interpolating between them.
+    pub fn keyframes(
+        keyframes: impl IntoIterator<Item = (f32, f32)>,
+    ) -> Result<Self, UnevenCoreError> {
+        Ok(Self::curve(UnevenSampleAutoCurve::new(keyframes)?))
+    }
+
+    /// Returns the attenuation between 0.0 and 1.0 to apply to a source that is `distance` meters away from the listener.
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        match self {
+            Self::Default => {
+                steam_audio_attenuation(&audionimbus::DistanceAttenuationModel::Default, distance)
+            }
+            Self::InverseDistance {
+                min_distance,
+                max_distance,
+            } => {
+                let model = audionimbus::DistanceAttenuationModel::InverseDistance {
+                    min_distance: *min_distance,
+                };
+                let Some(max_distance) = *max_distance else {
+                    return steam_audio_attenuation(&model, distance);
+                };
+                if distance >= max_distance {
+                    return 0.0;
+                }
+                let attenuation = steam_audio_attenuation(&model, distance);
+                let attenuation_at_max = steam_audio_attenuation(&model, max_distance);
+                if attenuation_at_max >= 1.0 {
+                    // `max_distance` is within `min_distance`, so there is no falloff to rescale
+                    return 1.0;
+                }
+                ((attenuation - attenuation_at_max) / (1.0 - attenuation_at_max)).clamp(0.0, 1.0)
+            }
+            Self::Curve(curve) => curve.0.sample_clamped(distance).clamp(0.0, 1.0),
+        }
+    }
+}
+
+fn steam_audio_attenuation(model: &audionimbus::DistanceAttenuationModel, distance: f32) -> f32 {
+    audionimbus::distance_attenuation(
+        &STEAM_AUDIO_CONTEXT,
+        audionimbus::Vector3::new(distance, 0.0, 0.0),
+        audionimbus::Vector3::new(0.0, 0.0, 0.0),
+        model,
+    )
+}
+
+/// A [`SteamAudioDistanceAttenuation`] shared with Steam Audio, which evaluates it through a callback.
+///
+/// The simulator may call into the model until the source using it is removed and the simulator is committed,
+/// so this must be kept alive until then.
+#[derive(Component, Clone, PartialEq)]
+pub(crate) struct SourceAttenuationModel(pub(crate) ArcGc<SteamAudioDistanceAttenuation>);
+
+impl Default for SourceAttenuationModel {
+    fn default() -> Self {
+        Self::new(SteamAudioDistanceAttenuation::Default)
+    }
+}
+
+impl SourceAttenuationModel {
+    pub(crate) fn new(model: SteamAudioDistanceAttenuation) -> Self {
+        Self(ArcGc::new(model))
+    }
+
+    /// Converts this into a model Steam Audio can use for simulation.
+    /// Set `dirty` if the model changed since the last simulation.
+    pub(crate) fn to_audionimbus(&self, dirty: bool) -> audionimbus::DistanceAttenuationModel {
+        match &*self.0 {
+            SteamAudioDistanceAttenuation::Default => {
+                audionimbus::DistanceAttenuationModel::Default
+            }
+            SteamAudioDistanceAttenuation::InverseDistance {
+                min_distance,
+                max_distance: None,
+            } => audionimbus::DistanceAttenuationModel::InverseDistance {
+                min_distance: *min_distance,
+            },
+            // Steam Audio cannot evaluate these models by itself
+            model => audionimbus::DistanceAttenuationModel::Callback {
+                callback: attenuation_callback,
+                user_data: core::ptr::from_ref(model).cast_mut().cast(),
+                dirty,
+            },
+        }
+    }
+}
+
+unsafe extern "C" fn attenuation_callback(distance: f32, user_data: *mut c_void) -> f32 {
+    // SAFETY: `user_data` points into a `SourceAttenuationModel`,
+    // which is kept alive for as long as the simulator may call this.
+    let model = unsafe {
+        &*user_data
+            .cast_const()
+            .cast::<SteamAudioDistanceAttenuation>()
+    };
+    model.attenuation(distance)
+}
+
+/// A curve mapping the distance in meters to an attenuation between 0.0 and 1.0.
+///
+/// The curve is shared with the audio thread, so cloning this is cheap.
+#[derive(Clone, PartialEq, RealtimeClone, Reflect)]
+#[reflect(opaque, Debug, Clone, PartialEq)]
+pub struct SteamAudioAttenuationCurve(ArcGc<dyn Curve<f32> + Send + Sync>);
+
+impl SteamAudioAttenuationCurve {
+    pub fn new(curve: impl Curve<f32> + Send + Sync + 'static) -> Self {
+        Self(ArcGc::new_unsized(|| {
+            Arc::new(curve) as Arc<dyn Curve<f32> + Send + Sync>
+        }))
+    }
+}
+
+impl core::fmt::Debug for SteamAudioAttenuationCurve {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("SteamAudioAttenuationCurve")
+            .field(&self.0.domain())
+            .finish()
+    }
+}
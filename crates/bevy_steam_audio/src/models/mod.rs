@@ -0,0 +1,11 @@
+pub(crate) mod air_absorption;
+pub(crate) mod distance_attenuation;
+
+pub use air_absorption::*;
+pub use distance_attenuation::*;
+
+use crate::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(air_absorption::plugin);
+}
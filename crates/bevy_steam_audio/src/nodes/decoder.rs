@@ -1,8 +1,10 @@
 use crate::{
     STEAM_AUDIO_CONTEXT,
+    error::report_processor_error,
     nodes::FixedProcessBlock,
     prelude::*,
-    settings::{SteamAudioQuality, order_to_num_channels},
+    settings::{SteamAudioOutputMode, SteamAudioQuality, order_to_num_channels},
+    simulation::node_quality,
     wrapper::{AudionimbusCoordinateSystem, ChannelPtrs},
 };
 
@@ -18,8 +20,8 @@ use firewheel::{
     diff::RealtimeClone,
     event::ProcEvents,
     node::{
-        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
-        ProcExtra, ProcInfo, ProcessStatus,
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, NodeID,
+        ProcBuffers, ProcExtra, ProcInfo, ProcessStatus,
     },
 };
 
@@ -43,16 +45,24 @@ pub struct AmbisonicDecodeNodeConfig {
     /// Set to `Some` if this is for some custom ambisonic audio you want to decode.
     pub order: Option<u32>,
     pub(crate) quality: SteamAudioQuality,
+    pub(crate) output: SteamAudioOutputMode,
+    pub(crate) latency_frames: u32,
 }
 
 fn on_add_decode_node_config(mut world: DeferredWorld, ctx: HookContext) {
-    let quality = *world.resource::<SteamAudioQuality>();
+    let (quality, latency_frames) = node_quality(&world);
+    let output = world
+        .get_resource::<SteamAudioOutputMode>()
+        .copied()
+        .unwrap_or_default();
     let mut entity = world.entity_mut(ctx.entity);
     let mut config = entity.get_mut::<AmbisonicDecodeNodeConfig>().unwrap();
     if config.order.is_none() {
         config.order = Some(quality.order);
     }
     config.quality = quality;
+    config.output = output;
+    config.latency_frames = latency_frames;
 }
 
 fn reset_steam_audio_decode_node(
@@ -69,8 +79,12 @@ fn reset_steam_audio_decode_node(
 }
 
 impl AmbisonicDecodeNodeConfig {
+    pub(crate) fn order(&self) -> u32 {
+        self.order.unwrap_or(self.quality.order)
+    }
+
     pub(crate) fn num_channels(&self) -> u32 {
-        order_to_num_channels(self.order.unwrap())
+        order_to_num_channels(self.order())
     }
 }
 
@@ -80,6 +94,7 @@ impl AudioNode for AmbisonicDecodeNode {
     fn info(&self, config: &Self::Configuration) -> AudioNodeInfo {
         AudioNodeInfo::new()
             .debug_name("Ambisonic decode node")
+            .latency_frames(config.latency_frames)
             .channel_config(ChannelConfig {
                 num_inputs: ChannelCount::new(config.num_channels()).unwrap(),
                 num_outputs: ChannelCount::STEREO,
@@ -95,17 +110,12 @@ impl AudioNode for AmbisonicDecodeNode {
             sampling_rate: cx.stream_info.sample_rate.get(),
             frame_size: config.quality.frame_size,
         };
-        let hrtf = audionimbus::Hrtf::try_new(
-            &STEAM_AUDIO_CONTEXT,
-            &settings,
-            &audionimbus::HrtfSettings {
-                volume_normalization: audionimbus::VolumeNormalization::RootMeanSquared,
-                ..default()
-            },
-        )
-        .unwrap();
+        let effects = SteamAudioDecodeEffects::try_new(&settings, config.order())
+            .map_err(|err| report_processor_error(cx.node_id, NODE_NAME, err))
+            .ok();
 
         SteamAudioDecodeProcessor {
+            node_id: cx.node_id,
             fixed_block: FixedProcessBlock::new(
                 config.quality.frame_size as usize,
                 cx.stream_info.max_block_frames.get() as usize,
@@ -113,30 +123,59 @@ impl AudioNode for AmbisonicDecodeNode {
                 2,
             ),
             params: self.clone(),
-            hrtf: hrtf.clone(),
-            ambisonics_decode_effect: audionimbus::AmbisonicsDecodeEffect::try_new(
-                &STEAM_AUDIO_CONTEXT,
-                &settings,
-                &audionimbus::AmbisonicsDecodeEffectSettings {
-                    max_order: config.order.unwrap(),
-                    speaker_layout: audionimbus::SpeakerLayout::Stereo,
-                    hrtf: &hrtf,
-                },
-            )
-            .unwrap(),
-            order: config.order.unwrap(),
+            effects,
+            order: config.order(),
             quality: config.quality,
+            output: config.output,
             mix_ptrs: ChannelPtrs::new(config.num_channels() as usize),
         }
     }
 }
 
+const NODE_NAME: &str = "Ambisonic decode node";
+
+struct SteamAudioDecodeEffects {
+    hrtf: audionimbus::Hrtf,
+    ambisonics_decode: audionimbus::AmbisonicsDecodeEffect,
+}
+
+impl SteamAudioDecodeEffects {
+    fn try_new(
+        settings: &audionimbus::AudioSettings,
+        order: u32,
+    ) -> Result<Self, audionimbus::SteamAudioError> {
+        let hrtf = audionimbus::Hrtf::try_new(
+            &STEAM_AUDIO_CONTEXT,
+            settings,
+            &audionimbus::HrtfSettings {
+                volume_normalization: audionimbus::VolumeNormalization::RootMeanSquared,
+                ..default()
+            },
+        )?;
+        let ambisonics_decode = audionimbus::AmbisonicsDecodeEffect::try_new(
+            &STEAM_AUDIO_CONTEXT,
+            settings,
+            &audionimbus::AmbisonicsDecodeEffectSettings {
+                max_order: order,
+                speaker_layout: audionimbus::SpeakerLayout::Stereo,
+                hrtf: &hrtf,
+            },
+        )?;
+        Ok(Self {
+            hrtf,
+            ambisonics_decode,
+        })
+    }
+}
+
 struct SteamAudioDecodeProcessor {
+    node_id: NodeID,
     fixed_block: FixedProcessBlock,
     params: AmbisonicDecodeNode,
-    hrtf: audionimbus::Hrtf,
-    ambisonics_decode_effect: audionimbus::AmbisonicsDecodeEffect,
+    /// `None` if the effects could not be created, in which case the node outputs silence.
+    effects: Option<SteamAudioDecodeEffects>,
     order: u32,
+    output: SteamAudioOutputMode,
     mix_ptrs: ChannelPtrs,
     quality: SteamAudioQuality,
 }
@@ -157,8 +196,10 @@ impl AudioNodeProcessor for SteamAudioDecodeProcessor {
         _: &mut ProcExtra,
     ) -> ProcessStatus {
         for patch in events.drain_patches::<AmbisonicDecodeNode>() {
-            if matches!(patch, AmbisonicDecodeNodePatch::Reset(..)) {
-                self.ambisonics_decode_effect.reset();
+            if matches!(patch, AmbisonicDecodeNodePatch::Reset(..))
+                && let Some(effects) = self.effects.as_mut()
+            {
+                effects.ambisonics_decode.reset();
             }
             Patch::apply(&mut self.params, patch);
         }
@@ -171,6 +212,11 @@ impl AudioNodeProcessor for SteamAudioDecodeProcessor {
             return ProcessStatus::ClearAllOutputs;
         }
 
+        let Some(effects) = self.effects.as_mut() else {
+            self.fixed_block.clear();
+            return ProcessStatus::ClearAllOutputs;
+        };
+
         let channels = self.num_channels();
         let fixed_block = &mut self.fixed_block;
         fixed_block.process(proc_buffers, proc_info, |inputs, outputs| {
@@ -191,7 +237,9 @@ impl AudioNodeProcessor for SteamAudioDecodeProcessor {
                     self.mix_ptrs.as_mut(),
                     self.quality.frame_size,
                 )
-                .unwrap()
+            };
+            let Ok(input_sa_buffer) = input_sa_buffer else {
+                return;
             };
 
             let (left, right) = outputs.split_at_mut(1);
@@ -210,16 +258,18 @@ impl AudioNodeProcessor for SteamAudioDecodeProcessor {
                     channel_ptrs.as_mut_slice(),
                     self.quality.frame_size,
                 )
-                .unwrap()
+            };
+            let Ok(output_sa_buffer) = output_sa_buffer else {
+                return;
             };
 
             let ambisonics_decode_effect_params = audionimbus::AmbisonicsDecodeEffectParams {
                 order: self.order,
-                hrtf: &self.hrtf,
+                hrtf: &effects.hrtf,
                 orientation: self.params.listener_orientation.into(),
-                binaural: true,
+                binaural: self.output.is_binaural(),
             };
-            let _effect_state = self.ambisonics_decode_effect.apply(
+            let _effect_state = effects.ambisonics_decode.apply(
                 &ambisonics_decode_effect_params,
                 &input_sa_buffer,
                 &output_sa_buffer,
@@ -242,27 +292,9 @@ impl AudioNodeProcessor for SteamAudioDecodeProcessor {
             sampling_rate: stream_info.sample_rate.get(),
             frame_size: self.quality.frame_size,
         };
-        let hrtf = audionimbus::Hrtf::try_new(
-            &STEAM_AUDIO_CONTEXT,
-            &settings,
-            &audionimbus::HrtfSettings {
-                volume_normalization: audionimbus::VolumeNormalization::RootMeanSquared,
-                ..default()
-            },
-        )
-        .unwrap();
-
-        self.hrtf = hrtf.clone();
-        self.ambisonics_decode_effect = audionimbus::AmbisonicsDecodeEffect::try_new(
-            &STEAM_AUDIO_CONTEXT,
-            &settings,
-            &audionimbus::AmbisonicsDecodeEffectSettings {
-                max_order: self.order,
-                speaker_layout: audionimbus::SpeakerLayout::Stereo,
-                hrtf: &hrtf,
-            },
-        )
-        .unwrap();
+        self.effects = SteamAudioDecodeEffects::try_new(&settings, self.order)
+            .map_err(|err| report_processor_error(self.node_id, NODE_NAME, err))
+            .ok();
 
         let fixed_block_size = self.fixed_block.inputs.channel_capacity;
         let max_output_size = stream_info.max_block_frames.get() as usize;
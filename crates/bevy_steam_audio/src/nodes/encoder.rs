@@ -1,8 +1,12 @@
 use crate::{
     STEAM_AUDIO_CONTEXT,
+    error::report_processor_error,
+    models::{SteamAudioAirAbsorption, SteamAudioDistanceAttenuation},
     nodes::{FixedProcessBlock, apply_volume_ramp},
     prelude::*,
-    settings::SteamAudioQuality,
+    settings::{SteamAudioHrtf, SteamAudioOutputMode, SteamAudioQuality},
+    simulation::node_quality,
+    sources::SteamAudioHrtfInterpolation,
     wrapper::{AudionimbusCoordinateSystem, ChannelPtrs, ToSteamAudioVec3 as _},
 };
 
@@ -31,16 +35,18 @@ pub(super) fn plugin(app: &mut App) {
 #[derive(Diff, Patch, Debug, PartialEq, Clone, RealtimeClone, Component, Reflect)]
 #[reflect(Component)]
 pub struct SteamAudioNode {
-    pub direct_gain: f32,
-    pub reflection_gain: f32,
-    pub pathing_gain: f32,
+    // Forwarded from `SteamAudioSource` every frame.
+    pub(crate) direct_gain: f32,
+    pub(crate) reflection_gain: f32,
+    pub(crate) pathing_gain: f32,
+    pub(crate) spatial_blend: f32,
+    pub(crate) hrtf_interpolation: SteamAudioHrtfInterpolation,
 
-    pub previous_direct_gain: f32,
-    pub previous_reflection_gain: f32,
-    pub previous_pathing_gain: f32,
     pub source_position: AudionimbusCoordinateSystem,
     pub listener_position: AudionimbusCoordinateSystem,
     pub pathing_available: bool,
+    pub distance_attenuation: SteamAudioDistanceAttenuation,
+    pub air_absorption: SteamAudioAirAbsorption,
     pub reset: Notify<()>,
 }
 
@@ -50,13 +56,14 @@ impl Default for SteamAudioNode {
             direct_gain: 1.0,
             reflection_gain: 1.0,
             pathing_gain: 1.0,
+            spatial_blend: 1.0,
+            hrtf_interpolation: SteamAudioHrtfInterpolation::default(),
 
-            previous_direct_gain: 0.0,
-            previous_reflection_gain: 0.0,
-            previous_pathing_gain: 0.0,
             source_position: AudionimbusCoordinateSystem::default(),
             listener_position: AudionimbusCoordinateSystem::default(),
             pathing_available: false,
+            distance_attenuation: SteamAudioDistanceAttenuation::default(),
+            air_absorption: SteamAudioAirAbsorption::default(),
             reset: Notify::default(),
         }
     }
@@ -69,13 +76,28 @@ pub struct SteamAudioNodeConfig {
     #[reflect(ignore)]
     pub(crate) hrtf: Option<audionimbus::Hrtf>,
     pub(crate) quality: SteamAudioQuality,
+    pub(crate) output: SteamAudioOutputMode,
+    pub(crate) latency_frames: u32,
 }
 
 fn on_add_steam_audio_node_config(mut world: DeferredWorld, ctx: HookContext) {
-    let quality = *world.resource::<SteamAudioQuality>();
+    let (quality, latency_frames) = node_quality(&world);
+    let output = world
+        .get_resource::<SteamAudioOutputMode>()
+        .copied()
+        .unwrap_or_default();
+    // Nodes spawned after `SteamAudioReady` would otherwise have to wait for the next simulator rebuild.
+    let hrtf = world
+        .get_resource::<SteamAudioHrtf>()
+        .map(|hrtf| hrtf.0.clone());
     let mut entity = world.entity_mut(ctx.entity);
     let mut config = entity.get_mut::<SteamAudioNodeConfig>().unwrap();
     config.quality = quality;
+    config.output = output;
+    config.latency_frames = latency_frames;
+    if config.hrtf.is_none() {
+        config.hrtf = hrtf;
+    }
 }
 
 fn reset_steam_audio_node(
@@ -94,9 +116,10 @@ fn reset_steam_audio_node(
 impl AudioNode for SteamAudioNode {
     type Configuration = SteamAudioNodeConfig;
 
-    fn info(&self, _config: &Self::Configuration) -> AudioNodeInfo {
+    fn info(&self, config: &Self::Configuration) -> AudioNodeInfo {
         AudioNodeInfo::new()
             .debug_name("Steam Audio node")
+            .latency_frames(config.latency_frames)
             .channel_config(ChannelConfig {
                 num_inputs: ChannelCount::STEREO,
                 num_outputs: ChannelCount::STEREO,
@@ -112,55 +135,17 @@ impl AudioNode for SteamAudioNode {
             sampling_rate: cx.stream_info.sample_rate.get(),
             frame_size: config.quality.frame_size,
         };
-        let hrtf = config.hrtf.clone().expect("Created an `AudioNode` before the audio stream was ready. Please wait until `SteamAudioReady` is triggered.");
+        // Without an HRTF, this node was created before the audio stream was ready.
+        // It will be rebuilt with one once `SteamAudioReady` is triggered.
+        let effects = config.hrtf.as_ref().and_then(|hrtf| {
+            SteamAudioEffects::try_new(&settings, &config.quality, hrtf)
+                .map_err(|err| report_processor_error(cx.node_id, NODE_NAME, err))
+                .ok()
+        });
         SteamAudioProcessor {
             params: self.clone(),
-
-            direct_effect: audionimbus::DirectEffect::try_new(
-                &STEAM_AUDIO_CONTEXT,
-                &settings,
-                &audionimbus::DirectEffectSettings { num_channels: 2 },
-            )
-            .unwrap(),
-            reflection_effect: audionimbus::ReflectionEffect::try_new(
-                &STEAM_AUDIO_CONTEXT,
-                &settings,
-                &audionimbus::ReflectionEffectSettings::Convolution {
-                    impulse_response_size: config
-                        .quality
-                        .impulse_response_size(settings.sampling_rate),
-                    num_channels: config.quality.num_channels(),
-                },
-            )
-            .unwrap(),
-            binaural_effect: audionimbus::BinauralEffect::try_new(
-                &STEAM_AUDIO_CONTEXT,
-                &settings,
-                &audionimbus::BinauralEffectSettings { hrtf: &hrtf },
-            )
-            .unwrap(),
-            pathing_effect: audionimbus::PathEffect::try_new(
-                &STEAM_AUDIO_CONTEXT,
-                &settings,
-                &audionimbus::PathEffectSettings {
-                    max_order: config.quality.order,
-                    spatialization: Some(audionimbus::Spatialization {
-                        speaker_layout: audionimbus::SpeakerLayout::Stereo,
-                        hrtf: &hrtf,
-                    }),
-                },
-            )
-            .unwrap(),
-            ambisonics_decode_effect: audionimbus::AmbisonicsDecodeEffect::try_new(
-                &STEAM_AUDIO_CONTEXT,
-                &settings,
-                &audionimbus::AmbisonicsDecodeEffectSettings {
-                    max_order: config.quality.order,
-                    speaker_layout: audionimbus::SpeakerLayout::Stereo,
-                    hrtf: &hrtf,
-                },
-            )
-            .unwrap(),
+            effects,
+            sampling_rate: cx.stream_info.sample_rate.get(),
             fixed_block: FixedProcessBlock::new(
                 config.quality.frame_size as usize,
                 cx.stream_info.max_block_frames.get() as usize,
@@ -168,8 +153,13 @@ impl AudioNode for SteamAudioNode {
                 2,
             ),
             source: None,
+            previous_gains: PreviousGains::default(),
             quality: config.quality,
-            hrtf,
+            output: config.output,
+            mono_direct_buffer: core::iter::repeat_n(0f32, config.quality.frame_size as usize)
+                .collect(),
+            mono_direct_ptrs: ChannelPtrs::new(1),
+            hrtf: config.hrtf.clone(),
             ambisonics_ptrs: ChannelPtrs::new(config.quality.num_channels() as usize),
             ambisonics_buffer: core::iter::repeat_n(
                 0f32,
@@ -180,22 +170,147 @@ impl AudioNode for SteamAudioNode {
     }
 }
 
+const NODE_NAME: &str = "Steam Audio node";
+
+/// Replaces the HRTF and effects of a running [`SteamAudioNode`], e.g. after a stream restart,
+/// so that it keeps playing instead of being rebuilt.
+///
+/// The effects are built on the main thread, so the audio thread only swaps them in.
+pub(crate) struct SteamAudioNodeEffects {
+    hrtf: Option<audionimbus::Hrtf>,
+    effects: Option<SteamAudioEffects>,
+    sampling_rate: u32,
+}
+
+impl SteamAudioNodeEffects {
+    pub(crate) fn try_new(
+        entity: Entity,
+        config: &SteamAudioNodeConfig,
+        sampling_rate: u32,
+    ) -> Result<Self, SteamAudioError> {
+        let settings = audionimbus::AudioSettings {
+            sampling_rate,
+            frame_size: config.quality.frame_size,
+        };
+        let effects = config
+            .hrtf
+            .as_ref()
+            .map(|hrtf| SteamAudioEffects::try_new(&settings, &config.quality, hrtf))
+            .transpose()
+            .map_err(|error| SteamAudioError::Processor {
+                entity,
+                node: NODE_NAME,
+                error,
+            })?;
+        Ok(Self {
+            hrtf: config.hrtf.clone(),
+            effects,
+            sampling_rate,
+        })
+    }
+}
+
+/// All Steam Audio effects used by [`SteamAudioProcessor`].
+struct SteamAudioEffects {
+    direct: audionimbus::DirectEffect,
+    reflection: audionimbus::ReflectionEffect,
+    binaural: audionimbus::BinauralEffect,
+    panning: audionimbus::PanningEffect,
+    pathing: audionimbus::PathEffect,
+    ambisonics_decode: audionimbus::AmbisonicsDecodeEffect,
+}
+
+impl SteamAudioEffects {
+    fn try_new(
+        settings: &audionimbus::AudioSettings,
+        quality: &SteamAudioQuality,
+        hrtf: &audionimbus::Hrtf,
+    ) -> Result<Self, audionimbus::SteamAudioError> {
+        Ok(Self {
+            direct: audionimbus::DirectEffect::try_new(
+                &STEAM_AUDIO_CONTEXT,
+                settings,
+                &audionimbus::DirectEffectSettings { num_channels: 2 },
+            )?,
+            reflection: audionimbus::ReflectionEffect::try_new(
+                &STEAM_AUDIO_CONTEXT,
+                settings,
+                &quality.reflection_effect_settings(settings.sampling_rate),
+            )?,
+            binaural: audionimbus::BinauralEffect::try_new(
+                &STEAM_AUDIO_CONTEXT,
+                settings,
+                &audionimbus::BinauralEffectSettings { hrtf },
+            )?,
+            panning: audionimbus::PanningEffect::try_new(
+                &STEAM_AUDIO_CONTEXT,
+                settings,
+                &audionimbus::PanningEffectSettings {
+                    speaker_layout: audionimbus::SpeakerLayout::Stereo,
+                },
+            )?,
+            pathing: audionimbus::PathEffect::try_new(
+                &STEAM_AUDIO_CONTEXT,
+                settings,
+                &audionimbus::PathEffectSettings {
+                    max_order: quality.order,
+                    spatialization: Some(audionimbus::Spatialization {
+                        speaker_layout: audionimbus::SpeakerLayout::Stereo,
+                        hrtf,
+                    }),
+                },
+            )?,
+            ambisonics_decode: audionimbus::AmbisonicsDecodeEffect::try_new(
+                &STEAM_AUDIO_CONTEXT,
+                settings,
+                &audionimbus::AmbisonicsDecodeEffectSettings {
+                    max_order: quality.order,
+                    speaker_layout: audionimbus::SpeakerLayout::Stereo,
+                    hrtf,
+                },
+            )?,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.direct.reset();
+        self.binaural.reset();
+        self.panning.reset();
+        self.reflection.reset();
+        self.pathing.reset();
+        self.ambisonics_decode.reset();
+    }
+}
+
+/// The gains of the last processed block, used to ramp towards the current gains.
+/// These start at zero so that new sounds fade in.
+#[derive(Default)]
+struct PreviousGains {
+    direct: f32,
+    reflection: f32,
+    pathing: f32,
+}
+
 struct SteamAudioProcessor {
     quality: SteamAudioQuality,
+    output: SteamAudioOutputMode,
     params: SteamAudioNode,
-    direct_effect: audionimbus::DirectEffect,
-    reflection_effect: audionimbus::ReflectionEffect,
-    binaural_effect: audionimbus::BinauralEffect,
-    pathing_effect: audionimbus::PathEffect,
-    ambisonics_decode_effect: audionimbus::AmbisonicsDecodeEffect,
+    /// `None` if the effects could not be created, in which case the input is passed through.
+    effects: Option<SteamAudioEffects>,
+    /// The sample rate the effects were created for.
+    sampling_rate: u32,
     fixed_block: FixedProcessBlock,
     source: Option<audionimbus::Source>,
+    previous_gains: PreviousGains,
     // We might be able to use the scratch buffers for this, but
     // the ambisonic order may produce more channels than scratch
     // buffers.
     ambisonics_buffer: Box<[f32]>,
     ambisonics_ptrs: ChannelPtrs,
-    hrtf: audionimbus::Hrtf,
+    // The panning effect only accepts mono input, so the direct path is downmixed into this.
+    mono_direct_buffer: Box<[f32]>,
+    mono_direct_ptrs: ChannelPtrs,
+    hrtf: Option<audionimbus::Hrtf>,
 }
 
 impl AudioNodeProcessor for SteamAudioProcessor {
@@ -209,15 +324,20 @@ impl AudioNodeProcessor for SteamAudioProcessor {
         for mut event in events.drain() {
             if let Some(patch) = SteamAudioNode::patch_event(&event) {
                 if matches!(patch, SteamAudioNodePatch::Reset(..)) {
-                    self.direct_effect.reset();
-                    self.binaural_effect.reset();
-                    self.reflection_effect.reset();
-                    self.pathing_effect.reset();
-                    self.ambisonics_decode_effect.reset();
+                    if let Some(effects) = self.effects.as_mut() {
+                        effects.reset();
+                    }
+                    self.previous_gains = PreviousGains::default();
                 }
                 Patch::apply(&mut self.params, patch);
             }
             event.downcast_swap(&mut self.source);
+            if let Some(replacement) = event.downcast_mut::<SteamAudioNodeEffects>() {
+                // The previous HRTF and effects are dropped along with the event, off the audio thread
+                core::mem::swap(&mut self.hrtf, &mut replacement.hrtf);
+                core::mem::swap(&mut self.effects, &mut replacement.effects);
+                self.sampling_rate = replacement.sampling_rate;
+            }
         }
 
         // If the previous output of this node was silent, and the inputs are also silent
@@ -226,6 +346,12 @@ impl AudioNodeProcessor for SteamAudioProcessor {
             return ProcessStatus::ClearAllOutputs;
         }
 
+        let (Some(effects), Some(hrtf)) = (self.effects.as_mut(), self.hrtf.as_ref()) else {
+            // We failed to create our effects, but it's better to hear the sound unspatialized than not at all.
+            self.fixed_block.clear();
+            return ProcessStatus::Bypass;
+        };
+
         let Some(mut source) = self.source.clone() else {
             // If this is encountered at any point other than just
             // after insertion into the graph, then something's gone
@@ -259,7 +385,9 @@ impl AudioNodeProcessor for SteamAudioProcessor {
             // will outlast `input_sa_buffer`.
             let input_sa_buffer = unsafe {
                 AudioBuffer::<&[f32], _>::try_new(channel_ptrs.as_mut_slice(), frame_size as u32)
-                    .unwrap()
+            };
+            let Ok(input_sa_buffer) = input_sa_buffer else {
+                return;
             };
 
             assert_eq!(outputs[0].len(), frame_size);
@@ -271,12 +399,14 @@ impl AudioNodeProcessor for SteamAudioProcessor {
             // SAFETY:
             // `channel_ptrs` points to `frame_size` floats, whose lifetime
             // will outlast `output_sa_buffer`.
-            let mut output_sa_buffer = unsafe {
+            let output_sa_buffer = unsafe {
                 AudioBuffer::<&mut [f32], _>::try_new(
                     channel_ptrs.as_mut_slice(),
                     frame_size as u32,
                 )
-                .unwrap()
+            };
+            let Ok(mut output_sa_buffer) = output_sa_buffer else {
+                return;
             };
 
             assert!(scratch_mono_reflect.len() >= frame_size);
@@ -284,12 +414,14 @@ impl AudioNodeProcessor for SteamAudioProcessor {
             // SAFETY:
             // `channel_ptrs` points to `frame_size` floats, whose lifetime
             // will outlast `mono_sa_buffer`.
-            let mut mono_reflect_sa_buffer = unsafe {
+            let mono_reflect_sa_buffer = unsafe {
                 AudioBuffer::<&mut [f32], _>::try_new(
                     channel_ptrs.as_mut_slice(),
                     frame_size as u32,
                 )
-                .unwrap()
+            };
+            let Ok(mut mono_reflect_sa_buffer) = mono_reflect_sa_buffer else {
+                return;
             };
             mono_reflect_sa_buffer.downmix(&STEAM_AUDIO_CONTEXT, &input_sa_buffer);
 
@@ -298,12 +430,14 @@ impl AudioNodeProcessor for SteamAudioProcessor {
             // SAFETY:
             // `channel_ptrs` points to `frame_size` floats, whose lifetime
             // will outlast `mono_sa_buffer`.
-            let mut mono_pathing_sa_buffer = unsafe {
+            let mono_pathing_sa_buffer = unsafe {
                 AudioBuffer::<&mut [f32], _>::try_new(
                     channel_ptrs.as_mut_slice(),
                     frame_size as u32,
                 )
-                .unwrap()
+            };
+            let Ok(mut mono_pathing_sa_buffer) = mono_pathing_sa_buffer else {
+                return;
             };
             mono_pathing_sa_buffer.downmix(&STEAM_AUDIO_CONTEXT, &input_sa_buffer);
 
@@ -325,7 +459,9 @@ impl AudioNodeProcessor for SteamAudioProcessor {
                     channel_ptrs.as_mut_slice(),
                     frame_size as u32,
                 )
-                .unwrap()
+            };
+            let Ok(scratch_stereo_sa_buffer) = scratch_stereo_sa_buffer else {
+                return;
             };
 
             let mut direct_effect_params = source
@@ -342,20 +478,14 @@ impl AudioNodeProcessor for SteamAudioProcessor {
                     power: 0.0,
                 },
             ));
-            direct_effect_params.distance_attenuation = Some(audionimbus::distance_attenuation(
-                &STEAM_AUDIO_CONTEXT,
-                source_position.origin.to_steam_audio_vec3(),
-                listener.origin.to_steam_audio_vec3(),
-                &audionimbus::DistanceAttenuationModel::Default,
-            ));
-            direct_effect_params.air_absorption = Some(audionimbus::air_absorption(
-                &STEAM_AUDIO_CONTEXT,
-                &source_position.origin.to_steam_audio_vec3(),
-                &listener.origin.to_steam_audio_vec3(),
-                &audionimbus::AirAbsorptionModel::Default,
+            let distance = source_position.origin.distance(listener.origin);
+            direct_effect_params.distance_attenuation =
+                Some(self.params.distance_attenuation.attenuation(distance));
+            direct_effect_params.air_absorption = Some(audionimbus::Equalizer(
+                self.params.air_absorption.air_absorption(distance),
             ));
 
-            let _effect_state = self.direct_effect.apply(
+            let _effect_state = effects.direct.apply(
                 &direct_effect_params,
                 &input_sa_buffer,
                 &scratch_stereo_sa_buffer,
@@ -370,25 +500,46 @@ impl AudioNodeProcessor for SteamAudioProcessor {
                 listener.up.to_steam_audio_vec3(),
             );
 
-            let binaural_params = audionimbus::BinauralEffectParams {
-                direction,
-                interpolation: audionimbus::HrtfInterpolation::Bilinear,
-                spatial_blend: 1.0,
-                hrtf: &self.hrtf,
-                peak_delays: None,
-            };
-
-            let _effect_state = self.binaural_effect.apply(
-                &binaural_params,
-                &scratch_stereo_sa_buffer,
-                &output_sa_buffer,
-            );
-            apply_volume_ramp(
-                self.params.previous_direct_gain,
-                self.params.direct_gain,
-                outputs,
-            );
-            self.params.previous_direct_gain = self.params.direct_gain;
+            if self.output.is_binaural() {
+                let binaural_params = audionimbus::BinauralEffectParams {
+                    direction,
+                    interpolation: self.params.hrtf_interpolation.into(),
+                    spatial_blend: self.params.spatial_blend,
+                    hrtf,
+                    peak_delays: None,
+                };
+
+                let _effect_state = effects.binaural.apply(
+                    &binaural_params,
+                    &scratch_stereo_sa_buffer,
+                    &output_sa_buffer,
+                );
+            } else {
+                let settings = audionimbus::AudioBufferSettings {
+                    num_channels: Some(1),
+                    frame_size: Some(frame_size as u32),
+                    ..default()
+                };
+                let Ok(mut mono_direct_sa_buffer) =
+                    AudioBuffer::try_borrowed_with_data_and_settings(
+                        &mut self.mono_direct_buffer,
+                        &mut self.mono_direct_ptrs,
+                        settings,
+                    )
+                else {
+                    return;
+                };
+                mono_direct_sa_buffer.downmix(&STEAM_AUDIO_CONTEXT, &scratch_stereo_sa_buffer);
+
+                let panning_params = audionimbus::PanningEffectParams { direction };
+                let _effect_state = effects.panning.apply(
+                    &panning_params,
+                    &mono_direct_sa_buffer,
+                    &output_sa_buffer,
+                );
+            }
+            apply_volume_ramp(self.previous_gains.direct, self.params.direct_gain, outputs);
+            self.previous_gains.direct = self.params.direct_gain;
 
             // Reflection effect
             let settings = audionimbus::AudioBufferSettings {
@@ -396,12 +547,13 @@ impl AudioNodeProcessor for SteamAudioProcessor {
                 frame_size: Some(frame_size as u32),
                 ..default()
             };
-            let ambisonics_sa_buffer = AudioBuffer::try_borrowed_with_data_and_settings(
+            let Ok(ambisonics_sa_buffer) = AudioBuffer::try_borrowed_with_data_and_settings(
                 &mut self.ambisonics_buffer,
                 &mut self.ambisonics_ptrs,
                 settings,
-            )
-            .unwrap();
+            ) else {
+                return;
+            };
 
             let mut reflection_effect_params = source
                 .get_outputs(audionimbus::SimulationFlags::REFLECTIONS)
@@ -409,18 +561,17 @@ impl AudioNodeProcessor for SteamAudioProcessor {
                 .into_inner();
             reflection_effect_params.reflection_effect_type = self.quality.reflections.kind.into();
             reflection_effect_params.num_channels = self.quality.num_channels();
-            reflection_effect_params.impulse_response_size = self
-                .quality
-                .impulse_response_size(proc_info.sample_rate.into());
+            reflection_effect_params.impulse_response_size =
+                self.quality.impulse_response_size(self.sampling_rate);
 
             apply_volume_ramp(
-                self.params.previous_reflection_gain,
+                self.previous_gains.reflection,
                 self.params.reflection_gain,
                 &mut [scratch_mono_reflect],
             );
-            self.params.previous_reflection_gain = self.params.reflection_gain;
+            self.previous_gains.reflection = self.params.reflection_gain;
 
-            let _effect_state = self.reflection_effect.apply(
+            let _effect_state = effects.reflection.apply(
                 &reflection_effect_params,
                 &mono_reflect_sa_buffer,
                 &ambisonics_sa_buffer,
@@ -429,11 +580,11 @@ impl AudioNodeProcessor for SteamAudioProcessor {
             // Decode ambisonics
             let ambisonics_decode_effect_params = audionimbus::AmbisonicsDecodeEffectParams {
                 order: self.quality.order,
-                hrtf: &self.hrtf,
+                hrtf,
                 orientation: listener.into(),
-                binaural: true,
+                binaural: self.output.is_binaural(),
             };
-            let _effect_state = self.ambisonics_decode_effect.apply(
+            let _effect_state = effects.ambisonics_decode.apply(
                 &ambisonics_decode_effect_params,
                 &ambisonics_sa_buffer,
                 &scratch_stereo_sa_buffer,
@@ -449,16 +600,16 @@ impl AudioNodeProcessor for SteamAudioProcessor {
                     .into_inner();
                 pathing_effect_params.order = self.quality.order;
                 pathing_effect_params.listener = listener.into();
-                pathing_effect_params.binaural = true;
-                pathing_effect_params.hrtf = self.hrtf.clone();
+                pathing_effect_params.binaural = self.output.is_binaural();
+                pathing_effect_params.hrtf = hrtf.clone();
 
                 apply_volume_ramp(
-                    self.params.previous_pathing_gain,
+                    self.previous_gains.pathing,
                     self.params.pathing_gain,
                     &mut [scratch_mono_pathing],
                 );
-                self.params.previous_pathing_gain = self.params.pathing_gain;
-                let _effect_state = self.pathing_effect.apply(
+                self.previous_gains.pathing = self.params.pathing_gain;
+                let _effect_state = effects.pathing.apply(
                     &pathing_effect_params,
                     &mono_pathing_sa_buffer,
                     &scratch_stereo_sa_buffer,
@@ -483,63 +634,12 @@ impl AudioNodeProcessor for SteamAudioProcessor {
         stream_info: &firewheel::StreamInfo,
         _context: &mut firewheel::node::ProcStreamCtx,
     ) {
-        // If these parameter don't change, there's no need to thrash the audio state.
-        if stream_info.sample_rate.get() == stream_info.prev_sample_rate.get()
-            && stream_info.max_block_frames.get() == self.fixed_block.max_block_frames() as u32
-        {
+        // When the sample rate changes, the current effects keep running until the simulator
+        // sends ones created for the new rate, so that playing sounds stay spatialized.
+        if stream_info.max_block_frames.get() == self.fixed_block.max_block_frames() as u32 {
             return;
         }
 
-        let settings = audionimbus::AudioSettings {
-            sampling_rate: stream_info.sample_rate.get(),
-            frame_size: self.fixed_block.frame_size() as u32,
-        };
-
-        self.direct_effect = audionimbus::DirectEffect::try_new(
-            &STEAM_AUDIO_CONTEXT,
-            &settings,
-            &audionimbus::DirectEffectSettings { num_channels: 2 },
-        )
-        .unwrap();
-        self.reflection_effect = audionimbus::ReflectionEffect::try_new(
-            &STEAM_AUDIO_CONTEXT,
-            &settings,
-            &audionimbus::ReflectionEffectSettings::Convolution {
-                impulse_response_size: self.quality.impulse_response_size(settings.sampling_rate),
-                num_channels: self.quality.num_channels(),
-            },
-        )
-        .unwrap();
-        self.pathing_effect = audionimbus::PathEffect::try_new(
-            &STEAM_AUDIO_CONTEXT,
-            &settings,
-            &audionimbus::PathEffectSettings {
-                max_order: self.quality.order,
-                spatialization: Some(audionimbus::Spatialization {
-                    speaker_layout: audionimbus::SpeakerLayout::Stereo,
-                    hrtf: &self.hrtf,
-                }),
-            },
-        )
-        .unwrap();
-        self.binaural_effect = audionimbus::BinauralEffect::try_new(
-            &STEAM_AUDIO_CONTEXT,
-            &settings,
-            &audionimbus::BinauralEffectSettings { hrtf: &self.hrtf },
-        )
-        .unwrap();
-
-        self.ambisonics_decode_effect = audionimbus::AmbisonicsDecodeEffect::try_new(
-            &STEAM_AUDIO_CONTEXT,
-            &settings,
-            &audionimbus::AmbisonicsDecodeEffectSettings {
-                max_order: self.quality.order,
-                speaker_layout: audionimbus::SpeakerLayout::Stereo,
-                hrtf: &self.hrtf,
-            },
-        )
-        .unwrap();
-
         let fixed_block_size = self.fixed_block.inputs.channel_capacity;
         let max_output_size = stream_info.max_block_frames.get() as usize;
         self.fixed_block.resize(fixed_block_size, max_output_size);
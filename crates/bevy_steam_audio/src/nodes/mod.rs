@@ -1,6 +1,6 @@
 use crate::prelude::*;
 use bevy_seedling::prelude::*;
-use core::iter;
+use core::{iter, num::NonZeroU32};
 use firewheel::node::{ProcBuffers, ProcInfo, ProcessStatus};
 use prealloc_ref_vec::{PreallocRefVec, TmpRefVec};
 
@@ -13,7 +13,6 @@ pub use encoder::*;
 pub use reverb::*;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(PreStartup, setup_nodes);
     app.add_plugins((encoder::plugin, reverb::plugin, decoder::plugin));
     app.register_required_components::<SteamAudioPool, Transform>()
         .register_required_components::<SteamAudioPool, GlobalTransform>();
@@ -28,19 +27,36 @@ pub struct SteamAudioReverbPool;
 #[derive(NodeLabel, PartialEq, Eq, Debug, Hash, Clone, Default)]
 pub struct SteamAudioReverbBus;
 
-pub(crate) fn setup_nodes(mut commands: Commands) {
+/// Configures the pools spawned by [`SteamAudioPlugin`](crate::SteamAudioPlugin).
+#[derive(Resource, Debug, Clone, Default)]
+pub struct SteamAudioDefaultPools {
+    /// The number of voices of the [`SteamAudioPool`].
+    /// If `None`, the [`DefaultPoolSize`] is used.
+    pub size: Option<PoolSize>,
+    /// The number of voices of the [`SteamAudioReverbPool`].
+    /// If `None`, the [`DefaultPoolSize`] is used.
+    pub reverb_size: Option<PoolSize>,
+}
+
+pub(crate) fn setup_nodes(mut commands: Commands, pools: Res<SteamAudioDefaultPools>) {
     // Copy-paste this part if you want to set up your own pool!
-    commands.spawn((
+    let mut pool = commands.spawn((
         SamplerPool(SteamAudioPool),
         sample_effects![SteamAudioNode::default()],
     ));
+    if let Some(size) = pools.size.clone() {
+        pool.insert(size);
+    }
 
     commands.spawn((SteamAudioReverbBus, SteamAudioReverbNode::default()));
 
-    commands.spawn((
+    let mut reverb_pool = commands.spawn((
         SamplerPool(SteamAudioReverbPool),
         sample_effects![SendNode::new(Volume::default(), SteamAudioReverbBus)],
     ));
+    if let Some(size) = pools.reverb_size.clone() {
+        reverb_pool.insert(size);
+    }
 }
 
 /// A helper to encapsulate processing audio in fixed blocks.
@@ -197,6 +213,20 @@ impl FixedProcessBlock {
         ProcessStatus::ClearAllOutputs
     }
 
+    /// The number of frames the output lags behind the input when host blocks of up to
+    /// `max_block_frames` are processed in fixed blocks of `frame_size`.
+    /// If the host block size is unknown, the worst case of a whole frame is assumed.
+    pub fn latency_frames(frame_size: u32, max_block_frames: Option<NonZeroU32>) -> u32 {
+        let Some(max_block_frames) = max_block_frames.map(NonZeroU32::get) else {
+            return frame_size;
+        };
+        // Outputs are only written once a whole host block of them has been processed.
+        frame_size
+            .saturating_sub(max_block_frames)
+            .div_ceil(max_block_frames)
+            * max_block_frames
+    }
+
     pub fn frame_size(&self) -> usize {
         self.inputs.channel_capacity
     }
@@ -290,3 +320,33 @@ fn apply_volume_ramp(start_volume: f32, end_volume: f32, buffer: &mut [&mut [f32
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latency(frame_size: u32, max_block_frames: u32) -> u32 {
+        FixedProcessBlock::latency_frames(frame_size, NonZeroU32::new(max_block_frames))
+    }
+
+    #[test]
+    fn latency_of_host_blocks_dividing_the_frame() {
+        // The fourth host block completes the frame, and its output is written right away
+        assert_eq!(latency(1024, 256), 768);
+        assert_eq!(latency(1024, 512), 512);
+        assert_eq!(latency(1024, 1024), 0);
+    }
+
+    #[test]
+    fn latency_of_host_blocks_not_dividing_the_frame() {
+        // The frame is completed during the third host block, so its output starts there
+        assert_eq!(latency(1024, 480), 960);
+        assert_eq!(latency(1024, 1000), 1000);
+        assert_eq!(latency(1024, 2048), 0);
+    }
+
+    #[test]
+    fn latency_without_host_block_size() {
+        assert_eq!(FixedProcessBlock::latency_frames(1024, None), 1024);
+    }
+}
@@ -12,8 +12,11 @@ use firewheel::{
 };
 
 use crate::{
+    error::report_processor_error,
     nodes::{FixedProcessBlock, apply_volume_ramp},
     prelude::*,
+    settings::SteamAudioHrtf,
+    simulation::node_quality,
     wrapper::{AudionimbusCoordinateSystem, ChannelPtrs},
 };
 
@@ -29,6 +32,8 @@ pub struct SteamAudioReverbNode {
     pub previous_gain: f32,
     pub listener_position: AudionimbusCoordinateSystem,
     pub reset: Notify<()>,
+    /// Set from [`SteamAudioEnabled::reverb`](crate::settings::SteamAudioEnabled::reverb).
+    pub(crate) enabled: bool,
 }
 
 impl Default for SteamAudioReverbNode {
@@ -38,6 +43,7 @@ impl Default for SteamAudioReverbNode {
             previous_gain: 1.0,
             listener_position: default(),
             reset: default(),
+            enabled: true,
         }
     }
 }
@@ -49,13 +55,28 @@ pub struct SteamAudioReverbNodeConfig {
     #[reflect(ignore)]
     pub(crate) hrtf: Option<audionimbus::Hrtf>,
     pub(crate) quality: SteamAudioQuality,
+    pub(crate) output: SteamAudioOutputMode,
+    pub(crate) latency_frames: u32,
 }
 
 fn on_add_steam_audio_reverb_node_config(mut world: DeferredWorld, ctx: HookContext) {
-    let quality = *world.resource::<SteamAudioQuality>();
+    let (quality, latency_frames) = node_quality(&world);
+    let output = world
+        .get_resource::<SteamAudioOutputMode>()
+        .copied()
+        .unwrap_or_default();
+    // Nodes spawned after `SteamAudioReady` would otherwise have to wait for the next simulator rebuild.
+    let hrtf = world
+        .get_resource::<SteamAudioHrtf>()
+        .map(|hrtf| hrtf.0.clone());
     let mut entity = world.entity_mut(ctx.entity);
     let mut config = entity.get_mut::<SteamAudioReverbNodeConfig>().unwrap();
     config.quality = quality;
+    config.output = output;
+    config.latency_frames = latency_frames;
+    if config.hrtf.is_none() {
+        config.hrtf = hrtf;
+    }
 }
 
 fn reset_reverb_node(
@@ -74,9 +95,10 @@ fn reset_reverb_node(
 impl AudioNode for SteamAudioReverbNode {
     type Configuration = SteamAudioReverbNodeConfig;
 
-    fn info(&self, _: &Self::Configuration) -> AudioNodeInfo {
+    fn info(&self, config: &Self::Configuration) -> AudioNodeInfo {
         AudioNodeInfo::new()
             .debug_name("Steam Audio reverb node")
+            .latency_frames(config.latency_frames)
             .channel_config(ChannelConfig {
                 num_inputs: ChannelCount::STEREO,
                 num_outputs: ChannelCount::STEREO,
@@ -88,27 +110,24 @@ impl AudioNode for SteamAudioReverbNode {
         config: &Self::Configuration,
         cx: ConstructProcessorContext,
     ) -> impl AudioNodeProcessor {
-        let hrtf = config.hrtf.clone().expect("Created an `AudioNode` before the audio stream was ready. Please wait until `SteamAudioReady` is triggered.");
         let settings = audionimbus::AudioSettings {
             sampling_rate: cx.stream_info.sample_rate.into(),
             frame_size: config.quality.frame_size,
         };
+        let effects = config.hrtf.as_ref().and_then(|hrtf| {
+            SteamAudioReverbEffects::try_new(&settings, &config.quality, hrtf)
+                .map_err(|err| report_processor_error(cx.node_id, NODE_NAME, err))
+                .ok()
+        });
         SteamAudioReverbNodeProcessor {
             source: None,
-            reflection_effect: audionimbus::ReflectionEffect::try_new(
-                &STEAM_AUDIO_CONTEXT,
-                &settings,
-                &audionimbus::ReflectionEffectSettings::Convolution {
-                    impulse_response_size: config
-                        .quality
-                        .impulse_response_size(cx.stream_info.sample_rate.into()),
-                    num_channels: config.quality.num_channels(),
-                },
-            )
-            .unwrap(),
+            effects,
+            sampling_rate: cx.stream_info.sample_rate.get(),
+            enabled_gain: if self.enabled { 1.0 } else { 0.0 },
             params: self.clone(),
             quality: config.quality,
-            hrtf: hrtf.clone(),
+            output: config.output,
+            hrtf: config.hrtf.clone(),
             ambisonics_ptrs: ChannelPtrs::new(config.quality.num_channels() as usize),
             ambisonics_buffer: core::iter::repeat_n(
                 0f32,
@@ -121,27 +140,97 @@ impl AudioNode for SteamAudioReverbNode {
                 2,
                 2,
             ),
-            ambisonics_decode_effect: audionimbus::AmbisonicsDecodeEffect::try_new(
+        }
+    }
+}
+
+const NODE_NAME: &str = "Steam Audio reverb node";
+
+/// Replaces the HRTF and effects of a running [`SteamAudioReverbNode`], e.g. after a stream restart,
+/// so that its reverb tail carries over instead of the node being rebuilt.
+///
+/// The effects are built on the main thread, so the audio thread only swaps them in.
+pub(crate) struct SteamAudioReverbNodeEffects {
+    hrtf: Option<audionimbus::Hrtf>,
+    effects: Option<SteamAudioReverbEffects>,
+    sampling_rate: u32,
+}
+
+impl SteamAudioReverbNodeEffects {
+    pub(crate) fn try_new(
+        entity: Entity,
+        config: &SteamAudioReverbNodeConfig,
+        sampling_rate: u32,
+    ) -> Result<Self, SteamAudioError> {
+        let settings = audionimbus::AudioSettings {
+            sampling_rate,
+            frame_size: config.quality.frame_size,
+        };
+        let effects = config
+            .hrtf
+            .as_ref()
+            .map(|hrtf| SteamAudioReverbEffects::try_new(&settings, &config.quality, hrtf))
+            .transpose()
+            .map_err(|error| SteamAudioError::Processor {
+                entity,
+                node: NODE_NAME,
+                error,
+            })?;
+        Ok(Self {
+            hrtf: config.hrtf.clone(),
+            effects,
+            sampling_rate,
+        })
+    }
+}
+
+struct SteamAudioReverbEffects {
+    reflection: audionimbus::ReflectionEffect,
+    ambisonics_decode: audionimbus::AmbisonicsDecodeEffect,
+}
+
+impl SteamAudioReverbEffects {
+    fn try_new(
+        settings: &audionimbus::AudioSettings,
+        quality: &SteamAudioQuality,
+        hrtf: &audionimbus::Hrtf,
+    ) -> Result<Self, audionimbus::SteamAudioError> {
+        Ok(Self {
+            reflection: audionimbus::ReflectionEffect::try_new(
                 &STEAM_AUDIO_CONTEXT,
-                &settings,
+                settings,
+                &quality.reflection_effect_settings(settings.sampling_rate),
+            )?,
+            ambisonics_decode: audionimbus::AmbisonicsDecodeEffect::try_new(
+                &STEAM_AUDIO_CONTEXT,
+                settings,
                 &audionimbus::AmbisonicsDecodeEffectSettings {
-                    max_order: config.quality.order,
+                    max_order: quality.order,
                     speaker_layout: audionimbus::SpeakerLayout::Stereo,
-                    hrtf: &hrtf,
+                    hrtf,
                 },
-            )
-            .unwrap(),
-        }
+            )?,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.reflection.reset();
+        self.ambisonics_decode.reset();
     }
 }
 
 struct SteamAudioReverbNodeProcessor {
     quality: SteamAudioQuality,
-    hrtf: audionimbus::Hrtf,
+    output: SteamAudioOutputMode,
+    hrtf: Option<audionimbus::Hrtf>,
     params: SteamAudioReverbNode,
+    /// The gain last applied for [`SteamAudioReverbNode::enabled`], used to fade the reverb in and out.
+    enabled_gain: f32,
     source: Option<audionimbus::Source>,
-    reflection_effect: audionimbus::ReflectionEffect,
-    ambisonics_decode_effect: audionimbus::AmbisonicsDecodeEffect,
+    /// `None` if the effects could not be created, in which case the node outputs silence.
+    effects: Option<SteamAudioReverbEffects>,
+    /// The sample rate the effects were created for.
+    sampling_rate: u32,
     // We might be able to use the scratch buffers for this, but
     // the ambisonic order may produce more channels than scratch
     // buffers.
@@ -160,13 +249,20 @@ impl AudioNodeProcessor for SteamAudioReverbNodeProcessor {
     ) -> ProcessStatus {
         for mut event in events.drain() {
             if let Some(patch) = SteamAudioReverbNode::patch_event(&event) {
-                if matches!(patch, SteamAudioReverbNodePatch::Reset(..)) {
-                    self.reflection_effect.reset();
-                    self.ambisonics_decode_effect.reset();
+                if matches!(patch, SteamAudioReverbNodePatch::Reset(..))
+                    && let Some(effects) = self.effects.as_mut()
+                {
+                    effects.reset();
                 }
                 Patch::apply(&mut self.params, patch);
             }
             event.downcast_swap(&mut self.source);
+            if let Some(replacement) = event.downcast_mut::<SteamAudioReverbNodeEffects>() {
+                // The previous HRTF and effects are dropped along with the event, off the audio thread
+                core::mem::swap(&mut self.hrtf, &mut replacement.hrtf);
+                core::mem::swap(&mut self.effects, &mut replacement.effects);
+                self.sampling_rate = replacement.sampling_rate;
+            }
         }
 
         // If the previous output of this node was silent, and the inputs are also silent
@@ -175,6 +271,15 @@ impl AudioNodeProcessor for SteamAudioReverbNodeProcessor {
             return ProcessStatus::ClearAllOutputs;
         }
 
+        // Once the reverb has been faded out, there is nothing left to process.
+        if !self.params.enabled && self.enabled_gain == 0.0 {
+            if let Some(effects) = self.effects.as_mut() {
+                effects.reset();
+            }
+            self.fixed_block.clear();
+            return ProcessStatus::ClearAllOutputs;
+        }
+
         let Some(mut source) = self.source.clone() else {
             // If this is encountered at any point other than just
             // after insertion into the graph, then something's gone
@@ -182,6 +287,10 @@ impl AudioNodeProcessor for SteamAudioReverbNodeProcessor {
             self.fixed_block.clear();
             return ProcessStatus::ClearAllOutputs;
         };
+        let (Some(effects), Some(hrtf)) = (self.effects.as_mut(), self.hrtf.as_ref()) else {
+            self.fixed_block.clear();
+            return ProcessStatus::ClearAllOutputs;
+        };
 
         let mut reflection_effect_params = source
             .get_outputs(audionimbus::SimulationFlags::REFLECTIONS)
@@ -206,7 +315,9 @@ impl AudioNodeProcessor for SteamAudioReverbNodeProcessor {
             // will outlast `input_sa_buffer`.
             let input_sa_buffer = unsafe {
                 AudioBuffer::<&[f32], _>::try_new(channel_ptrs.as_mut_slice(), frame_size as u32)
-                    .unwrap()
+            };
+            let Ok(input_sa_buffer) = input_sa_buffer else {
+                return;
             };
 
             assert_eq!(outputs[0].len(), frame_size);
@@ -223,7 +334,9 @@ impl AudioNodeProcessor for SteamAudioReverbNodeProcessor {
                     channel_ptrs.as_mut_slice(),
                     frame_size as u32,
                 )
-                .unwrap()
+            };
+            let Ok(output_sa_buffer) = output_sa_buffer else {
+                return;
             };
 
             assert!(scratch_mono.len() >= frame_size);
@@ -231,20 +344,21 @@ impl AudioNodeProcessor for SteamAudioReverbNodeProcessor {
             // SAFETY:
             // `channel_ptrs` points to `frame_size` floats, whose lifetime
             // will outlast `mono_sa_buffer`.
-            let mut mono_sa_buffer = unsafe {
+            let mono_sa_buffer = unsafe {
                 AudioBuffer::<&mut [f32], _>::try_new(
                     channel_ptrs.as_mut_slice(),
                     frame_size as u32,
                 )
-                .unwrap()
+            };
+            let Ok(mut mono_sa_buffer) = mono_sa_buffer else {
+                return;
             };
             mono_sa_buffer.downmix(&STEAM_AUDIO_CONTEXT, &input_sa_buffer);
 
             reflection_effect_params.reflection_effect_type = self.quality.reflections.kind.into();
             reflection_effect_params.num_channels = self.quality.num_channels();
-            reflection_effect_params.impulse_response_size = self
-                .quality
-                .impulse_response_size(proc_info.sample_rate.into());
+            reflection_effect_params.impulse_response_size =
+                self.quality.impulse_response_size(self.sampling_rate);
 
             apply_volume_ramp(
                 self.params.previous_gain,
@@ -258,14 +372,15 @@ impl AudioNodeProcessor for SteamAudioReverbNodeProcessor {
                 frame_size: Some(frame_size as u32),
                 ..default()
             };
-            let ambisonics_sa_buffer = AudioBuffer::try_borrowed_with_data_and_settings(
+            let Ok(ambisonics_sa_buffer) = AudioBuffer::try_borrowed_with_data_and_settings(
                 &mut self.ambisonics_buffer,
                 &mut self.ambisonics_ptrs,
                 settings,
-            )
-            .unwrap();
+            ) else {
+                return;
+            };
 
-            let _effect_state = self.reflection_effect.apply(
+            let _effect_state = effects.reflection.apply(
                 &reflection_effect_params,
                 &mono_sa_buffer,
                 &ambisonics_sa_buffer,
@@ -273,15 +388,19 @@ impl AudioNodeProcessor for SteamAudioReverbNodeProcessor {
 
             let decode_params = audionimbus::AmbisonicsDecodeEffectParams {
                 order: self.quality.order,
-                hrtf: &self.hrtf,
+                hrtf,
                 orientation: listener.into(),
-                binaural: true,
+                binaural: self.output.is_binaural(),
             };
-            let _effect_state = self.ambisonics_decode_effect.apply(
+            let _effect_state = effects.ambisonics_decode.apply(
                 &decode_params,
                 &ambisonics_sa_buffer,
                 &output_sa_buffer,
             );
+
+            let enabled_gain = if self.params.enabled { 1.0 } else { 0.0 };
+            apply_volume_ramp(self.enabled_gain, enabled_gain, outputs);
+            self.enabled_gain = enabled_gain;
         });
 
         // check for silence when the input is silent
@@ -299,36 +418,14 @@ impl AudioNodeProcessor for SteamAudioReverbNodeProcessor {
         stream_info: &firewheel::StreamInfo,
         _context: &mut firewheel::node::ProcStreamCtx,
     ) {
-        if stream_info.sample_rate.get() == stream_info.prev_sample_rate.get()
-            && stream_info.max_block_frames.get() == self.fixed_block.max_block_frames() as u32
-        {
+        // When the sample rate changes, the current effects keep running until the simulator
+        // sends ones created for the new rate, so that the reverb tail carries over.
+        if stream_info.max_block_frames.get() == self.fixed_block.max_block_frames() as u32 {
             return;
         }
 
-        let settings = audionimbus::AudioSettings {
-            sampling_rate: stream_info.sample_rate.get(),
-            frame_size: self.quality.frame_size,
-        };
-        self.reflection_effect = audionimbus::ReflectionEffect::try_new(
-            &STEAM_AUDIO_CONTEXT,
-            &settings,
-            &audionimbus::ReflectionEffectSettings::Convolution {
-                impulse_response_size: self
-                    .quality
-                    .impulse_response_size(stream_info.sample_rate.into()),
-                num_channels: self.quality.num_channels(),
-            },
-        )
-        .unwrap();
-        self.ambisonics_decode_effect = audionimbus::AmbisonicsDecodeEffect::try_new(
-            &STEAM_AUDIO_CONTEXT,
-            &settings,
-            &audionimbus::AmbisonicsDecodeEffectSettings {
-                max_order: self.quality.order,
-                speaker_layout: audionimbus::SpeakerLayout::Stereo,
-                hrtf: &self.hrtf,
-            },
-        )
-        .unwrap();
+        let fixed_block_size = self.fixed_block.inputs.channel_capacity;
+        let max_output_size = stream_info.max_block_frames.get() as usize;
+        self.fixed_block.resize(fixed_block_size, max_output_size);
     }
 }
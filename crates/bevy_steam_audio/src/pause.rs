@@ -0,0 +1,113 @@
+use bevy_seedling::{pool::label::PoolLabelContainer, prelude::*};
+
+use crate::{
+    nodes::{SteamAudioNode, SteamAudioReverbNode},
+    prelude::*,
+    settings::SteamAudioPauseMode,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    let schedule = SteamAudioSchedule::from_app(app);
+    app.add_systems(
+        schedule,
+        follow_virtual_time_pause.in_set(SteamAudioSystems::UpdateSources),
+    );
+}
+
+/// Whether the simulation is frozen because [`Time<Virtual>`] is paused.
+pub(crate) fn simulation_frozen(mode: Res<SteamAudioPauseMode>, time: Res<Time<Virtual>>) -> bool {
+    *mode != SteamAudioPauseMode::Ignore && time.is_paused()
+}
+
+/// The volume a spatialized pool had before it was ducked.
+#[derive(Component)]
+struct VolumeBeforePause(Volume);
+
+/// Marks samples that were paused by [`SteamAudioPauseMode::Pause`].
+#[derive(Component)]
+struct PausedBySteamAudio;
+
+/// Pools and sample players are spatialized if any of their effects is a Steam Audio node.
+/// This includes the default pools as well as any custom ones.
+fn follow_virtual_time_pause(
+    mode: Res<SteamAudioPauseMode>,
+    time: Res<Time<Virtual>>,
+    mut pools: Query<
+        (
+            Entity,
+            &mut VolumeNode,
+            &SampleEffects,
+            Option<&VolumeBeforePause>,
+        ),
+        (With<PoolLabelContainer>, Without<SamplePlayer>),
+    >,
+    mut samples: Query<
+        (
+            Entity,
+            &mut PlaybackSettings,
+            Option<&SampleEffects>,
+            Has<PausedBySteamAudio>,
+        ),
+        With<SamplePlayer>,
+    >,
+    mut nodes: Query<&mut SteamAudioNode>,
+    mut reverb_nodes: Query<&mut SteamAudioReverbNode>,
+    mut was_paused: Local<bool>,
+    mut commands: Commands,
+) {
+    let paused = simulation_frozen(Res::clone(&mode), Res::clone(&time));
+    let spatialized = |effects: &SampleEffects| {
+        effects
+            .iter()
+            .any(|&effect| nodes.contains(effect) || reverb_nodes.contains(effect))
+    };
+
+    // Checked every frame so that mode changes and pools or samples spawned while paused are handled too.
+    let duck_volume = match *mode {
+        SteamAudioPauseMode::Duck { volume } if paused => Some(volume),
+        _ => None,
+    };
+    for (entity, mut volume_node, effects, volume_before_pause) in &mut pools {
+        let duck_volume = duck_volume.filter(|_| spatialized(effects));
+        match (duck_volume, volume_before_pause) {
+            (Some(volume), None) => {
+                commands
+                    .entity(entity)
+                    .try_insert(VolumeBeforePause(volume_node.volume));
+                volume_node.volume = volume;
+            }
+            (Some(volume), Some(_)) => {
+                if volume_node.volume != volume {
+                    volume_node.volume = volume;
+                }
+            }
+            (None, Some(volume_before_pause)) => {
+                volume_node.volume = volume_before_pause.0;
+                commands.entity(entity).try_remove::<VolumeBeforePause>();
+            }
+            (None, None) => {}
+        }
+    }
+
+    let pause_samples = paused && *mode == SteamAudioPauseMode::Pause;
+    for (entity, mut settings, effects, paused_by_us) in &mut samples {
+        if pause_samples && !paused_by_us && *settings.play && effects.is_some_and(spatialized) {
+            settings.pause();
+            commands.entity(entity).try_insert(PausedBySteamAudio);
+        } else if !pause_samples && paused_by_us {
+            settings.play();
+            commands.entity(entity).try_remove::<PausedBySteamAudio>();
+        }
+    }
+
+    if *was_paused && !paused {
+        // The effects still hold the state from before the pause, which no longer matches the simulation.
+        for mut node in &mut nodes {
+            node.reset.notify();
+        }
+        for mut reverb_node in &mut reverb_nodes {
+            reverb_node.reset.notify();
+        }
+    }
+    *was_paused = paused;
+}
@@ -7,8 +7,9 @@ use crate::{
 };
 
 pub(super) fn plugin(app: &mut App) {
+    let schedule = SteamAudioSchedule::from_app(app);
     app.add_systems(
-        PostUpdate,
+        schedule,
         generate_probes
             .in_set(SteamAudioSystems::GenerateProbes)
             // Important to have a run condition to not try to lock the simulator every frame
@@ -46,15 +47,15 @@ fn generate_probes(
     probe_batch: Option<Res<SteamAudioProbeBatch>>,
     pathing_settings: Res<SteamAudioPathBakingSettings>,
     quality: Res<SteamAudioQuality>,
-) -> Result {
+) {
     let mut global_aabb = None;
     let Some(generate) = generate_probes.drain().last() else {
-        return Ok(());
+        return;
     };
     let Ok(mut simulator) = simulator.get().try_write() else {
         // Simulator is in use, try again next frame
         generate_probes.write(generate);
-        return Ok(());
+        return;
     };
     let aabb = if let Some(aabb) = generate.aabb {
         aabb
@@ -82,15 +83,27 @@ fn generate_probes(
         height: generate.height,
         transform,
     };
-    let mut array = audionimbus::ProbeArray::try_new(&STEAM_AUDIO_CONTEXT)?;
+    let mut array = match audionimbus::ProbeArray::try_new(&STEAM_AUDIO_CONTEXT) {
+        Ok(array) => array,
+        Err(err) => {
+            commands.trigger(SteamAudioError::Baking { error: err });
+            return;
+        }
+    };
     array.generate_probes(&root, &params);
     if array.num_probes() == 0 {
         error!("Failed to generate any probes. Is the scene empty?");
-        return Ok(());
+        return;
     }
     debug!("Generated {} probes", array.num_probes());
 
-    let mut batch = audionimbus::ProbeBatch::try_new(&STEAM_AUDIO_CONTEXT)?;
+    let mut batch = match audionimbus::ProbeBatch::try_new(&STEAM_AUDIO_CONTEXT) {
+        Ok(batch) => batch,
+        Err(err) => {
+            commands.trigger(SteamAudioError::Baking { error: err });
+            return;
+        }
+    };
     batch.add_probe_array(&array);
     batch.commit();
 
@@ -123,8 +136,6 @@ fn generate_probes(
     );
 
     commands.insert_resource(SteamAudioProbeBatch(batch));
-
-    Ok(())
 }
 
 unsafe extern "C" fn progress_callback(progress: f32, _user_data: *mut std::ffi::c_void) {
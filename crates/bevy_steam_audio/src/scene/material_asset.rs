@@ -0,0 +1,96 @@
+use bevy_asset::{AssetLoader, LoadContext, io::Reader};
+use bevy_platform::collections::HashSet;
+
+use crate::{error::MaterialAssetError, prelude::*};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<SteamAudioMaterialAsset>()
+        .init_asset_loader::<SteamAudioMaterialAssetLoader>();
+    app.add_systems(
+        SteamAudioSchedule::from_app(app),
+        sync_material_assets.in_set(SteamAudioSystems::MeshLifecycle),
+    );
+}
+
+/// A [`SteamAudioMaterial`] loaded from a `.steam_audio_material.ron` file, e.g.
+///
+/// ```ron
+/// (
+///     absorption: (0.11, 0.07, 0.06),
+///     scattering: 0.05,
+///     transmission: (0.070, 0.014, 0.005),
+/// )
+/// ```
+///
+/// Reference it with [`SteamAudioMaterialHandle`]. When the file changes, e.g. through hot reloading,
+/// all meshes using it are respawned with the new material.
+#[derive(
+    Asset,
+    TypePath,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Deref,
+    DerefMut,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(transparent)]
+pub struct SteamAudioMaterialAsset(pub SteamAudioMaterial);
+
+/// Keeps the [`SteamAudioMaterial`] of this entity in sync with a [`SteamAudioMaterialAsset`].
+/// The material is inserted once the asset is loaded.
+#[derive(Component, Debug, Clone, PartialEq, Deref, DerefMut, Reflect)]
+#[reflect(Component)]
+pub struct SteamAudioMaterialHandle(pub Handle<SteamAudioMaterialAsset>);
+
+/// Loads [`SteamAudioMaterialAsset`]s from RON.
+#[derive(Default)]
+pub struct SteamAudioMaterialAssetLoader;
+
+impl AssetLoader for SteamAudioMaterialAssetLoader {
+    type Asset = SteamAudioMaterialAsset;
+    type Settings = ();
+    type Error = MaterialAssetError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["steam_audio_material.ron"]
+    }
+}
+
+fn sync_material_assets(
+    mut asset_events: MessageReader<AssetEvent<SteamAudioMaterialAsset>>,
+    handles: Query<(Entity, Ref<SteamAudioMaterialHandle>)>,
+    assets: Res<Assets<SteamAudioMaterialAsset>>,
+    mut commands: Commands,
+) {
+    let changed = asset_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+    for (entity, handle) in &handles {
+        if !handle.is_changed() && !changed.contains(&handle.id()) {
+            continue;
+        }
+        let Some(asset) = assets.get(&handle.0) else {
+            // Not loaded yet, we will get an event once it is
+            continue;
+        };
+        commands.entity(entity).try_insert(asset.0);
+    }
+}
@@ -0,0 +1,64 @@
+use wildmatch::WildMatch;
+
+use crate::prelude::*;
+
+/// Maps names, such as asset paths, to [`SteamAudioMaterial`]s with case-insensitive wildcard patterns.
+///
+/// The default mapping recognizes common material names, e.g. every name containing "wood" maps to [`SteamAudioMaterial::WOOD`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SteamAudioMaterialMapping {
+    /// The patterns and their materials, tried in order. `*` matches any number of characters and `?` a single one.
+    pub patterns: Vec<(WildMatch, SteamAudioMaterial)>,
+    /// The material of names that match no pattern.
+    pub default_material: SteamAudioMaterial,
+}
+
+impl Default for SteamAudioMaterialMapping {
+    fn default() -> Self {
+        Self::empty()
+            .map_material("*brick*", SteamAudioMaterial::BRICK)
+            .map_material("*concrete*", SteamAudioMaterial::CONCRETE)
+            .map_material("*ceramic*", SteamAudioMaterial::CERAMIC)
+            .map_material("*gravel*", SteamAudioMaterial::GRAVEL)
+            .map_material("*carpet*", SteamAudioMaterial::CARPET)
+            .map_material("*glass*", SteamAudioMaterial::GLASS)
+            .map_material("*plaster*", SteamAudioMaterial::PLASTER)
+            .map_material("*wood*", SteamAudioMaterial::WOOD)
+            .map_material("*metal*", SteamAudioMaterial::METAL)
+            .map_material("*rock*", SteamAudioMaterial::ROCK)
+    }
+}
+
+impl SteamAudioMaterialMapping {
+    /// A mapping without patterns, so every name maps to [`Self::default_material`].
+    pub fn empty() -> Self {
+        Self {
+            patterns: Vec::new(),
+            default_material: SteamAudioMaterial::default(),
+        }
+    }
+
+    /// Appends a pattern. It is tried after all patterns added before it.
+    pub fn map_material(mut self, pattern: impl AsRef<str>, material: SteamAudioMaterial) -> Self {
+        self.patterns
+            .push((WildMatch::new_case_insensitive(pattern.as_ref()), material));
+        self
+    }
+
+    /// Sets [`Self::default_material`].
+    pub fn with_default_material(mut self, material: SteamAudioMaterial) -> Self {
+        self.default_material = material;
+        self
+    }
+
+    /// Returns the material of the first pattern matching any of the `names`, or [`Self::default_material`].
+    pub fn material_for<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str> + Clone,
+    ) -> SteamAudioMaterial {
+        self.patterns
+            .iter()
+            .find(|(pattern, _)| names.clone().into_iter().any(|name| pattern.matches(name)))
+            .map_or(self.default_material, |(_, material)| *material)
+    }
+}
@@ -1,16 +1,32 @@
 use std::marker::PhantomData;
 
-use bevy_platform::collections::HashMap;
+use bevy_platform::collections::{HashMap, HashSet};
 
 use crate::{
     prelude::*,
     scene::{
-        InSteamAudioMeshSpawnQueue, Static, SteamAudioInstancedMesh, SteamAudioRootScene,
-        SteamAudioStaticMesh,
+        InSteamAudioMeshSpawnQueue, NotSteamAudioMesh, Static, SteamAudioInstancedMesh,
+        SteamAudioRootScene, SteamAudioStaticMesh,
     },
-    wrapper::{ToSteamAudioMesh as _, ToSteamAudioTransform},
+    wrapper::{SteamAudioMaterials, ToSteamAudioMesh as _, ToSteamAudioTransform},
 };
 
+#[cfg(feature = "gltf")]
+mod gltf_extras;
+mod inheritance;
+#[cfg(feature = "standard_material")]
+mod material_mapping;
+#[cfg(feature = "gltf")]
+pub use gltf_extras::{GLTF_EXTRAS_KEY, SteamAudioGltfExtras};
+pub(crate) use inheritance::InheritedSteamAudio;
+#[cfg(feature = "standard_material")]
+pub use material_mapping::Mesh3dSteamAudioMaterialMapping;
+
+/// Turns every [`Mesh3d`] with a [`SteamAudioMaterial`] into Steam Audio geometry.
+///
+/// [`SteamAudioMaterial`], [`Static`] and [`NotSteamAudioMesh`] inserted on an entity also apply to all of its descendant meshes,
+/// e.g. when inserted on a [`SceneRoot`](https://docs.rs/bevy/latest/bevy/prelude/struct.SceneRoot.html).
+/// Components on a mesh itself or on a closer ancestor take precedence.
 pub struct Mesh3dSteamAudioScenePlugin {
     _pd: PhantomData<()>,
 }
@@ -24,7 +40,7 @@ impl Default for Mesh3dSteamAudioScenePlugin {
 impl Plugin for Mesh3dSteamAudioScenePlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
-            PostUpdate,
+            SteamAudioSchedule::from_app(app),
             (
                 // if we modified or removed a mesh, first despawn it on steam audio's side
                 garbage_collect_meshes,
@@ -35,21 +51,84 @@ impl Plugin for Mesh3dSteamAudioScenePlugin {
                 spawn_new_steam_audio_meshes,
             )
                 .chain()
-                .in_set(SteamAudioSystems::MeshLifecycle),
+                .in_set(SteamAudioSystems::MeshLifecycle)
+                // Not present if the Steam Audio context could not be created
+                .run_if(resource_exists::<SteamAudioRootScene>),
         );
         app.init_resource::<MeshToScene>();
+        app.add_observer(remove_sub_scene_user);
+        app.add_plugins(inheritance::plugin);
+        #[cfg(feature = "gltf")]
+        app.add_plugins(gltf_extras::plugin);
+        #[cfg(feature = "standard_material")]
+        app.add_plugins(material_mapping::plugin);
     }
 }
 
+/// Marks a [`SteamAudioMaterial`] as derived by the `Mesh3dSteamAudioMaterialMapping`, so it is updated when the mapping changes.
+/// Remove it to keep the current material.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct MappedSteamAudioMaterial;
+
+/// Sub-scenes are shared by all instances of a mesh with the same materials.
 #[derive(Resource, Default, Deref, DerefMut)]
-struct MeshToScene(HashMap<AssetId<Mesh>, audionimbus::Scene>);
+struct MeshToScene(HashMap<SubSceneKey, audionimbus::Scene>);
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct SubSceneKey {
+    mesh: AssetId<Mesh>,
+    materials: Vec<u32>,
+}
+
+impl SubSceneKey {
+    fn new(
+        mesh: AssetId<Mesh>,
+        material: &SteamAudioMaterial,
+        materials: &SteamAudioMaterials,
+    ) -> Self {
+        let materials = material
+            .to_bits()
+            .into_iter()
+            .chain(
+                materials
+                    .materials
+                    .iter()
+                    .flat_map(SteamAudioMaterial::to_bits),
+            )
+            .chain(
+                materials
+                    .index_ranges
+                    .iter()
+                    .flat_map(|range| [range.indices.start, range.indices.end, range.material]),
+            )
+            .collect();
+        Self { mesh, materials }
+    }
+}
+
+/// The cached sub-scene an instanced mesh uses, so that sub-scenes no longer in use can be dropped.
+#[derive(Component)]
+struct UsesSubScene(SubSceneKey);
 
 fn queue_steam_audio_mesh_processing(
-    meshes: Query<(Entity, Ref<Mesh3d>), With<SteamAudioMaterial>>,
+    meshes: Query<
+        (
+            Entity,
+            Ref<Mesh3d>,
+            Ref<SteamAudioMaterial>,
+            Option<Ref<SteamAudioMaterials>>,
+        ),
+        Without<NotSteamAudioMesh>,
+    >,
     mut commands: Commands,
 ) {
-    for (entity, mesh) in meshes.iter() {
-        if mesh.is_changed() {
+    for (entity, mesh, material, materials) in meshes.iter() {
+        // Steam Audio cannot change the materials of an existing mesh, so it is rebuilt instead
+        if mesh.is_changed()
+            || material.is_changed()
+            || materials.is_some_and(|materials| materials.is_changed())
+        {
             commands
                 .entity(entity)
                 .try_insert(InSteamAudioMeshSpawnQueue);
@@ -63,20 +142,20 @@ fn spawn_new_steam_audio_meshes(
     queued: Query<
         (
             Entity,
-            NameOrEntity,
             &Mesh3d,
             &SteamAudioMaterial,
+            Option<&SteamAudioMaterials>,
             &GlobalTransform,
             Has<Static>,
         ),
-        With<InSteamAudioMeshSpawnQueue>,
+        (With<InSteamAudioMeshSpawnQueue>, Without<NotSteamAudioMesh>),
     >,
     meshes: Res<Assets<Mesh>>,
     mut root: ResMut<SteamAudioRootScene>,
-    mut errors: Local<Vec<String>>,
-) -> Result {
-    errors.clear();
-    for (entity, name, mesh_handle, material, transform, is_static) in &queued {
+) {
+    let no_materials = SteamAudioMaterials::default();
+    for (entity, mesh_handle, material, materials, transform, is_static) in &queued {
+        let materials = materials.unwrap_or(&no_materials);
         let id = mesh_handle.id();
         let Some(mesh) = meshes.get(id) else {
             // mesh not loaded yet
@@ -84,7 +163,8 @@ fn spawn_new_steam_audio_meshes(
         };
 
         if !is_static {
-            let sub_scene = if let Some(sub_scene) = map.get(&id) {
+            let key = SubSceneKey::new(id, material, materials);
+            let sub_scene = if let Some(sub_scene) = map.get(&key) {
                 sub_scene.clone()
             } else {
                 let mut sub_scene = match audionimbus::Scene::try_new(
@@ -93,19 +173,21 @@ fn spawn_new_steam_audio_meshes(
                 ) {
                     Ok(sub_scene) => sub_scene,
                     Err(err) => {
-                        errors.push(format!(
-                            "{name}: Failed to create sub-scene for mesh: {err}"
-                        ));
+                        commands.trigger(SteamAudioError::SubScene { entity, error: err });
                         commands
                             .entity(entity)
                             .try_remove::<InSteamAudioMeshSpawnQueue>();
                         continue;
                     }
                 };
-                let static_mesh = match mesh.to_steam_audio_mesh(&sub_scene, (*material).into()) {
+                let static_mesh = match mesh.to_steam_audio_mesh_with_materials(
+                    &sub_scene,
+                    (*material).into(),
+                    materials,
+                ) {
                     Ok(mesh) => mesh,
                     Err(err) => {
-                        errors.push(format!("{name}: Failed to convert mesh: {err}"));
+                        commands.trigger(SteamAudioError::MeshConversion { entity, error: err });
                         commands
                             .entity(entity)
                             .try_remove::<InSteamAudioMeshSpawnQueue>();
@@ -115,7 +197,7 @@ fn spawn_new_steam_audio_meshes(
                 sub_scene.add_static_mesh(static_mesh);
                 // committing a new scene should be fine during simulation of a different scene
                 sub_scene.commit();
-                map.insert(id, sub_scene.clone());
+                map.insert(key.clone(), sub_scene.clone());
                 sub_scene
             };
             let transform = transform.to_steam_audio_transform();
@@ -128,7 +210,7 @@ fn spawn_new_steam_audio_meshes(
                 match audionimbus::InstancedMesh::try_new(&root, instanced_mesh_settings) {
                     Ok(instanced_mesh) => instanced_mesh,
                     Err(err) => {
-                        errors.push(format!("{name}: Failed to create instanced mesh: {err}"));
+                        commands.trigger(SteamAudioError::InstancedMesh { entity, error: err });
                         commands
                             .entity(entity)
                             .try_remove::<InSteamAudioMeshSpawnQueue>();
@@ -138,22 +220,26 @@ fn spawn_new_steam_audio_meshes(
             root.add_instanced_mesh(instanced_mesh.clone());
             commands
                 .entity(entity)
-                .try_insert(SteamAudioInstancedMesh(instanced_mesh));
+                .try_remove::<SteamAudioStaticMesh>()
+                .try_insert((SteamAudioInstancedMesh(instanced_mesh), UsesSubScene(key)));
         } else {
             let mesh = mesh.clone().transformed_by(transform.compute_transform());
-            let static_mesh = match mesh.to_steam_audio_mesh(&root, (*material).into()) {
-                Ok(mesh) => mesh,
-                Err(err) => {
-                    errors.push(format!("{name}: Failed to convert mesh: {err}"));
-                    commands
-                        .entity(entity)
-                        .try_remove::<InSteamAudioMeshSpawnQueue>();
-                    continue;
-                }
-            };
+            let static_mesh =
+                match mesh.to_steam_audio_mesh_with_materials(&root, (*material).into(), materials)
+                {
+                    Ok(mesh) => mesh,
+                    Err(err) => {
+                        commands.trigger(SteamAudioError::MeshConversion { entity, error: err });
+                        commands
+                            .entity(entity)
+                            .try_remove::<InSteamAudioMeshSpawnQueue>();
+                        continue;
+                    }
+                };
             root.add_static_mesh(static_mesh.clone());
             commands
                 .entity(entity)
+                .try_remove::<SteamAudioInstancedMesh>()
                 .try_insert(SteamAudioStaticMesh(static_mesh));
         }
 
@@ -166,7 +252,7 @@ fn spawn_new_steam_audio_meshes(
             let gizmo = match SteamAudioGizmo::try_from(mesh) {
                 Ok(gizmo) => gizmo,
                 Err(err) => {
-                    errors.push(format!("{name}: Failed to create Gizmo: {err}"));
+                    commands.trigger(SteamAudioError::Gizmo { entity, error: err });
                     continue;
                 }
             };
@@ -175,21 +261,27 @@ fn spawn_new_steam_audio_meshes(
         }
     }
     // Do not call root.commit(), it's not safe while simulations are running
+}
 
-    if !errors.is_empty() {
-        Err(errors.join("\n").into())
-    } else {
-        Ok(())
-    }
+fn remove_sub_scene_user(remove: On<Remove, SteamAudioInstancedMesh>, mut commands: Commands) {
+    commands.entity(remove.entity).try_remove::<UsesSubScene>();
 }
 
 fn garbage_collect_meshes(
     mut asset_events: MessageReader<AssetEvent<Mesh>>,
     mut map: ResMut<MeshToScene>,
+    users: Query<&UsesSubScene>,
+    changed_users: Query<(), Changed<UsesSubScene>>,
+    mut removed_users: RemovedComponents<UsesSubScene>,
 ) {
     for event in asset_events.read() {
         if let AssetEvent::Removed { id } | AssetEvent::Modified { id } = event {
-            map.remove(id);
+            map.retain(|key, _| key.mesh != *id);
         }
     }
+    // Otherwise, animating a material would leave behind a sub-scene for every value it went through
+    if removed_users.read().count() > 0 || !changed_users.is_empty() {
+        let used = users.iter().map(|user| &user.0).collect::<HashSet<_>>();
+        map.retain(|key, _| used.contains(key));
+    }
 }
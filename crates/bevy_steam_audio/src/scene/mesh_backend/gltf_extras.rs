@@ -0,0 +1,293 @@
+use bevy_gltf::{GltfExtras, GltfMaterialExtras};
+use serde::Deserialize;
+
+use crate::{
+    error::GltfExtrasError,
+    prelude::*,
+    scene::{NotSteamAudioMesh, Static},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    // The extras take precedence over inherited components and the material mapping
+    let system = apply_gltf_extras
+        .before(super::garbage_collect_meshes)
+        .before(super::inheritance::inherit_components);
+    #[cfg(feature = "standard_material")]
+    let system = system.before(super::material_mapping::map_materials);
+    app.add_systems(
+        SteamAudioSchedule::from_app(app),
+        system.in_set(SteamAudioSystems::MeshLifecycle),
+    );
+}
+
+/// The key of the extras block read from glTF nodes and materials.
+///
+/// In Blender, add it as a custom property on an object or material, e.g.
+///
+/// ```json
+/// "steam_audio": {
+///     "preset": "brick",
+///     "absorption": [0.03, 0.04, 0.07],
+///     "scattering": 0.05,
+///     "transmission": [0.015, 0.015, 0.015],
+///     "static": true,
+///     "exclude": false
+/// }
+/// ```
+///
+/// See [`SteamAudioGltfExtras`] for the meaning of each field.
+pub const GLTF_EXTRAS_KEY: &str = "steam_audio";
+
+/// The acoustic properties of a glTF node or material, read from the [`GLTF_EXTRAS_KEY`] block of its extras.
+///
+/// Meshes with such a block get a [`SteamAudioMaterial`], unless they are excluded.
+/// If both a node and its material have the block, the fields of the node take precedence.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct SteamAudioGltfExtras {
+    /// The name of a [`SteamAudioMaterial`] constant the other material fields start from,
+    /// as in [`SteamAudioMaterial::from_preset_name`]. Defaults to [`SteamAudioMaterial::GENERIC`].
+    pub preset: Option<String>,
+    /// Overrides [`SteamAudioMaterial::absorption`].
+    pub absorption: Option<[f32; 3]>,
+    /// Overrides [`SteamAudioMaterial::scattering`].
+    pub scattering: Option<f32>,
+    /// Overrides [`SteamAudioMaterial::transmission`].
+    pub transmission: Option<[f32; 3]>,
+    /// Whether to insert [`Static`].
+    #[serde(rename = "static")]
+    pub is_static: Option<bool>,
+    /// Whether to insert [`NotSteamAudioMesh`] instead of a [`SteamAudioMaterial`].
+    pub exclude: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct Extras {
+    // Keep in sync with `GLTF_EXTRAS_KEY`
+    steam_audio: Option<SteamAudioGltfExtras>,
+}
+
+impl SteamAudioGltfExtras {
+    /// Reads the [`GLTF_EXTRAS_KEY`] block from the JSON extras of a glTF node or material, if there is one.
+    pub fn from_json(extras: &str) -> Result<Option<Self>, GltfExtrasError> {
+        Ok(serde_json::from_str::<Extras>(extras)?.steam_audio)
+    }
+
+    /// Fills the fields missing in `self` from `other`.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            preset: self.preset.or(other.preset),
+            absorption: self.absorption.or(other.absorption),
+            scattering: self.scattering.or(other.scattering),
+            transmission: self.transmission.or(other.transmission),
+            is_static: self.is_static.or(other.is_static),
+            exclude: self.exclude.or(other.exclude),
+        }
+    }
+
+    /// Builds the [`SteamAudioMaterial`] described by these extras.
+    pub fn material(&self) -> Result<SteamAudioMaterial, GltfExtrasError> {
+        let mut material = match &self.preset {
+            Some(preset) => SteamAudioMaterial::from_preset_name(preset)
+                .ok_or_else(|| GltfExtrasError::UnknownPreset(preset.clone()))?,
+            None => SteamAudioMaterial::GENERIC,
+        };
+        if let Some(absorption) = self.absorption {
+            material.absorption = absorption;
+        }
+        if let Some(scattering) = self.scattering {
+            material.scattering = scattering;
+        }
+        if let Some(transmission) = self.transmission {
+            material.transmission = transmission;
+        }
+        Ok(material)
+    }
+}
+
+fn apply_gltf_extras(
+    meshes: Query<
+        (
+            Entity,
+            Option<&GltfExtras>,
+            Option<&GltfMaterialExtras>,
+            Option<&ChildOf>,
+        ),
+        Added<Mesh3d>,
+    >,
+    nodes: Query<&GltfExtras>,
+    mut commands: Commands,
+) {
+    for (entity, extras, material_extras, child_of) in &meshes {
+        // glTF primitives are spawned as children of their node
+        let node_extras =
+            extras.or_else(|| child_of.and_then(|child_of| nodes.get(child_of.parent()).ok()));
+        let values = [
+            node_extras.map(|extras| extras.value.as_str()),
+            material_extras.map(|extras| extras.value.as_str()),
+        ];
+        let extras = match values
+            .into_iter()
+            .flatten()
+            .map(SteamAudioGltfExtras::from_json)
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(extras) => extras,
+            Err(error) => {
+                commands.trigger(SteamAudioError::GltfExtras { entity, error });
+                continue;
+            }
+        };
+        let Some(extras) = extras
+            .into_iter()
+            .flatten()
+            .reduce(SteamAudioGltfExtras::or)
+        else {
+            continue;
+        };
+
+        if extras.exclude == Some(true) {
+            commands.entity(entity).try_insert(NotSteamAudioMesh);
+            continue;
+        }
+        let material = match extras.material() {
+            Ok(material) => material,
+            Err(error) => {
+                commands.trigger(SteamAudioError::GltfExtras { entity, error });
+                continue;
+            }
+        };
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.try_insert(material);
+        if extras.is_static == Some(true) {
+            entity_commands.try_insert(Static);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_extras_without_block() {
+        assert_eq!(SteamAudioGltfExtras::from_json("{}").unwrap(), None);
+        assert_eq!(
+            SteamAudioGltfExtras::from_json(r#"{"other": 1}"#).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn reads_preset() {
+        let extras = SteamAudioGltfExtras::from_json(r#"{"steam_audio": {"preset": "Brick"}}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(extras.material().unwrap(), SteamAudioMaterial::BRICK);
+        assert_eq!(extras.is_static, None);
+        assert_eq!(extras.exclude, None);
+    }
+
+    #[test]
+    fn reads_custom_material() {
+        let extras = SteamAudioGltfExtras::from_json(
+            r#"{"steam_audio": {
+                "preset": "wood",
+                "absorption": [0.1, 0.2, 0.3],
+                "transmission": [0.4, 0.5, 0.6]
+            }}"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            extras.material().unwrap(),
+            SteamAudioMaterial {
+                absorption: [0.1, 0.2, 0.3],
+                scattering: SteamAudioMaterial::WOOD.scattering,
+                transmission: [0.4, 0.5, 0.6],
+            }
+        );
+
+        let extras = SteamAudioGltfExtras::from_json(r#"{"steam_audio": {"scattering": 0.7}}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            extras.material().unwrap(),
+            SteamAudioMaterial {
+                scattering: 0.7,
+                ..SteamAudioMaterial::GENERIC
+            }
+        );
+    }
+
+    #[test]
+    fn reads_static_and_exclude() {
+        let extras = SteamAudioGltfExtras::from_json(
+            r#"{"steam_audio": {"static": true, "exclude": false}}"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(extras.is_static, Some(true));
+        assert_eq!(extras.exclude, Some(false));
+
+        let extras = SteamAudioGltfExtras::from_json(r#"{"steam_audio": {"exclude": true}}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(extras.exclude, Some(true));
+        assert_eq!(extras.is_static, None);
+    }
+
+    #[test]
+    fn node_takes_precedence_over_material() {
+        let node = SteamAudioGltfExtras {
+            preset: Some("glass".to_string()),
+            is_static: Some(false),
+            ..default()
+        };
+        let material = SteamAudioGltfExtras {
+            preset: Some("rock".to_string()),
+            scattering: Some(0.9),
+            is_static: Some(true),
+            ..default()
+        };
+        assert_eq!(
+            node.or(material),
+            SteamAudioGltfExtras {
+                preset: Some("glass".to_string()),
+                scattering: Some(0.9),
+                is_static: Some(false),
+                ..default()
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_extras() {
+        for json in [
+            "not json",
+            r#"{"steam_audio": "brick"}"#,
+            r#"{"steam_audio": {"absorption": [0.1, 0.2]}}"#,
+            r#"{"steam_audio": {"scattering": "high"}}"#,
+            r#"{"steam_audio": {"static": "yes"}}"#,
+        ] {
+            assert!(
+                matches!(
+                    SteamAudioGltfExtras::from_json(json),
+                    Err(GltfExtrasError::Json(_))
+                ),
+                "{json}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_preset() {
+        let extras = SteamAudioGltfExtras::from_json(r#"{"steam_audio": {"preset": "cheese"}}"#)
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            extras.material(),
+            Err(GltfExtrasError::UnknownPreset(preset)) if preset == "cheese"
+        ));
+    }
+}
@@ -0,0 +1,192 @@
+use bevy_platform::collections::HashSet;
+
+use crate::{
+    prelude::*,
+    scene::{
+        InSteamAudioMeshSpawnQueue, NotSteamAudioMesh, Static,
+        mesh_backend::MappedSteamAudioMaterial,
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    let system = inherit_components.before(super::garbage_collect_meshes);
+    // Inherited materials take precedence over the material mapping
+    #[cfg(feature = "standard_material")]
+    let system = system.before(super::material_mapping::map_materials);
+    app.add_systems(
+        SteamAudioSchedule::from_app(app),
+        system.in_set(SteamAudioSystems::MeshLifecycle),
+    );
+}
+
+/// Tracks which components of a [`Mesh3d`] were inherited from an ancestor rather than inserted on it directly.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct InheritedSteamAudio {
+    /// The inherited material. If the mesh's material differs, it was overwritten directly.
+    material: Option<SteamAudioMaterial>,
+    pub(crate) is_static: bool,
+    exclude: bool,
+}
+
+/// What the nearest ancestor with an opinion says about a mesh's surface.
+#[derive(Clone, Copy, PartialEq)]
+enum Surface {
+    Material(SteamAudioMaterial),
+    Excluded,
+}
+
+/// Propagates [`SteamAudioMaterial`], [`Static`] and [`NotSteamAudioMesh`] from ancestors to every descendant [`Mesh3d`].
+/// Components inserted on a mesh or a closer ancestor take precedence.
+pub(super) fn inherit_components(
+    changed: Query<
+        Entity,
+        Or<(
+            Changed<ChildOf>,
+            Added<Mesh3d>,
+            Changed<SteamAudioMaterial>,
+            Added<Static>,
+            Added<NotSteamAudioMesh>,
+        )>,
+    >,
+    mut removed: (
+        RemovedComponents<ChildOf>,
+        RemovedComponents<SteamAudioMaterial>,
+        RemovedComponents<Static>,
+        RemovedComponents<NotSteamAudioMesh>,
+    ),
+    meshes: Query<
+        (
+            Entity,
+            Option<&SteamAudioMaterial>,
+            Has<Static>,
+            Has<NotSteamAudioMesh>,
+            Has<MappedSteamAudioMaterial>,
+            Option<&InheritedSteamAudio>,
+        ),
+        With<Mesh3d>,
+    >,
+    ancestors: Query<(
+        Option<&SteamAudioMaterial>,
+        Has<Static>,
+        Has<NotSteamAudioMesh>,
+        Has<MappedSteamAudioMaterial>,
+        Option<&InheritedSteamAudio>,
+    )>,
+    parents: Query<&ChildOf>,
+    children: Query<&Children>,
+    mut commands: Commands,
+) {
+    // Only the changed entities and their descendants can inherit something different
+    let mut affected = HashSet::default();
+    for entity in changed
+        .iter()
+        .chain(removed.0.read())
+        .chain(removed.1.read())
+        .chain(removed.2.read())
+        .chain(removed.3.read())
+    {
+        if affected.insert(entity) {
+            affected.extend(children.iter_descendants(entity));
+        }
+    }
+
+    for entity in affected {
+        let Ok((entity, material, is_static, is_excluded, is_mapped, inherited)) =
+            meshes.get(entity)
+        else {
+            continue;
+        };
+        let inherited = inherited.copied().unwrap_or_default();
+        let own_surface =
+            (material.is_some() && material != inherited.material.as_ref() && !is_mapped)
+                || (is_excluded && !inherited.exclude);
+        let own_static = is_static && !inherited.is_static;
+
+        let mut surface = None;
+        let mut ancestor_static = false;
+        for ancestor in parents.iter_ancestors(entity) {
+            let Ok((
+                ancestor_material,
+                ancestor_is_static,
+                ancestor_is_excluded,
+                ancestor_is_mapped,
+                ancestor_inherited,
+            )) = ancestors.get(ancestor)
+            else {
+                continue;
+            };
+            let ancestor_inherited = ancestor_inherited.copied().unwrap_or_default();
+            if surface.is_none() {
+                if ancestor_is_excluded && !ancestor_inherited.exclude {
+                    surface = Some(Surface::Excluded);
+                } else if let Some(material) = ancestor_material
+                    && Some(material) != ancestor_inherited.material.as_ref()
+                    && !ancestor_is_mapped
+                {
+                    surface = Some(Surface::Material(*material));
+                }
+            }
+            ancestor_static |= ancestor_is_static && !ancestor_inherited.is_static;
+            if surface.is_some() && ancestor_static {
+                break;
+            }
+        }
+
+        let mut next = inherited;
+        let mut entity_commands = commands.entity(entity);
+        if !own_surface {
+            match surface {
+                Some(Surface::Material(ancestor_material)) => {
+                    if inherited.exclude {
+                        entity_commands.try_remove::<NotSteamAudioMesh>();
+                    }
+                    if material != Some(&ancestor_material) {
+                        entity_commands
+                            .try_remove::<MappedSteamAudioMaterial>()
+                            .try_insert(ancestor_material);
+                    }
+                    next.material = Some(ancestor_material);
+                    next.exclude = false;
+                }
+                Some(Surface::Excluded) => {
+                    if !is_excluded {
+                        entity_commands.try_insert(NotSteamAudioMesh);
+                    }
+                    // An inherited material stays tracked, so it is removed or replaced once the exclusion ends
+                    next.exclude = true;
+                }
+                None => {
+                    if inherited.material.is_some() {
+                        entity_commands.try_remove::<SteamAudioMaterial>();
+                    }
+                    if inherited.exclude {
+                        entity_commands.try_remove::<NotSteamAudioMesh>();
+                    }
+                    next.material = None;
+                    next.exclude = false;
+                }
+            }
+        } else {
+            next.material = None;
+            next.exclude = false;
+        }
+        if !own_static {
+            if ancestor_static && !is_static {
+                entity_commands.try_insert((Static, InSteamAudioMeshSpawnQueue));
+            } else if !ancestor_static && inherited.is_static {
+                entity_commands
+                    .try_remove::<Static>()
+                    .try_insert(InSteamAudioMeshSpawnQueue);
+            }
+            next.is_static = ancestor_static;
+        }
+
+        if next != inherited {
+            if next == InheritedSteamAudio::default() {
+                entity_commands.try_remove::<InheritedSteamAudio>();
+            } else {
+                entity_commands.try_insert(next);
+            }
+        }
+    }
+}
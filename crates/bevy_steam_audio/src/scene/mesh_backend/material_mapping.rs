@@ -0,0 +1,104 @@
+use bevy_asset::LoadState;
+use bevy_gltf::GltfMaterialName;
+use bevy_pbr::{MeshMaterial3d, StandardMaterial};
+
+use crate::{
+    prelude::*,
+    scene::{NotSteamAudioMesh, mesh_backend::MappedSteamAudioMaterial},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        SteamAudioSchedule::from_app(app),
+        map_materials
+            .before(super::garbage_collect_meshes)
+            .in_set(SteamAudioSystems::MeshLifecycle)
+            .run_if(resource_exists::<Mesh3dSteamAudioMaterialMapping>),
+    );
+}
+
+/// Derives a [`SteamAudioMaterial`] for every [`Mesh3d`] with a [`MeshMaterial3d<StandardMaterial>`] that does not have one yet.
+///
+/// Not inserted by default. Insert it to enable the mapping.
+///
+/// The patterns are matched against the [`StandardMaterial`]'s asset path, its [`GltfMaterialName`] and the asset path of its base color texture.
+/// The first pattern matching any of them wins.
+#[derive(Resource, Debug, Clone, PartialEq, Default, Deref, DerefMut)]
+pub struct Mesh3dSteamAudioMaterialMapping(pub SteamAudioMaterialMapping);
+
+pub(super) fn map_materials(
+    unmapped: Query<
+        (
+            Entity,
+            &MeshMaterial3d<StandardMaterial>,
+            Option<&GltfMaterialName>,
+        ),
+        (
+            With<Mesh3d>,
+            Without<SteamAudioMaterial>,
+            Without<NotSteamAudioMesh>,
+        ),
+    >,
+    mapped: Query<
+        (
+            Entity,
+            Ref<MeshMaterial3d<StandardMaterial>>,
+            Option<&GltfMaterialName>,
+            &SteamAudioMaterial,
+        ),
+        With<MappedSteamAudioMaterial>,
+    >,
+    mapping: Res<Mesh3dSteamAudioMaterialMapping>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let names = |handle: &MeshMaterial3d<StandardMaterial>,
+                 gltf_name: Option<&GltfMaterialName>|
+     -> Option<Vec<String>> {
+        let standard_material = standard_materials.get(handle);
+        if standard_material.is_none()
+            && !matches!(
+                asset_server.get_load_state(handle.id()),
+                Some(LoadState::Failed(_))
+            )
+        {
+            // Wait for the material to load so its textures are known
+            return None;
+        }
+        let texture = standard_material.and_then(|material| material.base_color_texture.as_ref());
+        Some(
+            [
+                handle.path().map(ToString::to_string),
+                gltf_name.map(|name| name.0.clone()),
+                texture.and_then(Handle::path).map(ToString::to_string),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+        )
+    };
+
+    for (entity, handle, gltf_name) in &unmapped {
+        let Some(names) = names(handle, gltf_name) else {
+            continue;
+        };
+        let material = mapping.material_for(names.iter().map(String::as_str));
+        commands
+            .entity(entity)
+            .try_insert((material, MappedSteamAudioMaterial));
+    }
+
+    for (entity, handle, gltf_name, current) in &mapped {
+        if !mapping.is_changed() && !handle.is_changed() {
+            continue;
+        }
+        let Some(names) = names(&handle, gltf_name) else {
+            continue;
+        };
+        let material = mapping.material_for(names.iter().map(String::as_str));
+        if material != *current {
+            commands.entity(entity).try_insert(material);
+        }
+    }
+}
@@ -1,17 +1,33 @@
+use core::time::Duration;
+
 use bevy_ecs::entity_disabling::Disabled;
 
-use crate::{STEAM_AUDIO_CONTEXT, prelude::*, wrapper::ToSteamAudioTransform as _};
+use crate::{
+    STEAM_AUDIO_CONTEXT, prelude::*, scene::mesh_backend::InheritedSteamAudio,
+    wrapper::ToSteamAudioTransform as _,
+};
 
+#[cfg(feature = "material_asset")]
+pub mod material_asset;
+#[cfg(feature = "material_mapping")]
+pub mod material_mapping;
 pub mod mesh_backend;
 
 pub(super) fn plugin(app: &mut App) {
+    let schedule = SteamAudioSchedule::from_app(app);
     app.init_resource::<SteamAudioRootScene>();
+    #[cfg(feature = "material_asset")]
+    app.add_plugins(material_asset::plugin);
     app.add_observer(remove_material)
+        .add_observer(exclude_mesh)
+        .add_observer(include_mesh)
         .add_observer(remove_dynamic_mesh_from_scene)
-        .add_observer(remove_static_mesh_from_scene);
+        .add_observer(remove_static_mesh_from_scene)
+        .add_observer(forget_static_mesh_moves);
     app.add_systems(
-        PostUpdate,
-        update_transforms.in_set(SteamAudioSystems::UpdateTransforms),
+        schedule,
+        (update_transforms, respawn_moved_static_meshes)
+            .in_set(SteamAudioSystems::UpdateTransforms),
     );
 }
 
@@ -30,10 +46,18 @@ impl Default for SteamAudioRootScene {
     }
 }
 
+/// Bakes the mesh's transform into its geometry, which is cheaper to simulate than an instanced mesh.
+/// Moving a static mesh respawns it, so only use this for meshes that do not move after level setup.
 #[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
 #[reflect(Component)]
 pub struct Static;
 
+/// Keeps a mesh out of the acoustic scene, even if a scene backend would otherwise derive a [`SteamAudioMaterial`] for it.
+/// Its [`SteamAudioMaterial`] is kept, so removing this component brings the mesh back.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct NotSteamAudioMesh;
+
 #[derive(Component)]
 pub struct SteamAudioInstancedMesh(pub audionimbus::InstancedMesh);
 
@@ -47,6 +71,27 @@ fn remove_material(remove: On<Remove, SteamAudioMaterial>, mut commands: Command
         .try_remove::<SteamAudioStaticMesh>();
 }
 
+fn exclude_mesh(add: On<Add, NotSteamAudioMesh>, mut commands: Commands) {
+    // The material is kept so that the mesh comes back when it is no longer excluded
+    commands
+        .entity(add.entity)
+        .try_remove::<InSteamAudioMeshSpawnQueue>()
+        .try_remove::<SteamAudioInstancedMesh>()
+        .try_remove::<SteamAudioStaticMesh>();
+}
+
+fn include_mesh(
+    remove: On<Remove, NotSteamAudioMesh>,
+    materials: Query<(), With<SteamAudioMaterial>>,
+    mut commands: Commands,
+) {
+    if materials.contains(remove.entity) {
+        commands
+            .entity(remove.entity)
+            .try_insert(InSteamAudioMeshSpawnQueue);
+    }
+}
+
 fn remove_dynamic_mesh_from_scene(
     remove: On<Replace, SteamAudioInstancedMesh>,
     instanced_mesh: Query<&SteamAudioInstancedMesh, Allow<Disabled>>,
@@ -86,3 +131,79 @@ fn update_transforms(
 #[derive(Component, Default, Reflect)]
 #[reflect(Component)]
 pub struct InSteamAudioMeshSpawnQueue;
+
+/// How many moves of a [`Static`] mesh within [`STATIC_MOVE_WINDOW`] trigger a warning.
+const STATIC_MOVES_BEFORE_WARNING: u32 = 10;
+const STATIC_MOVE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Counts how often a [`Static`] mesh was respawned because it moved.
+#[derive(Component)]
+struct StaticMeshMoves {
+    window_start: Duration,
+    count: u32,
+    warned: bool,
+}
+
+fn forget_static_mesh_moves(remove: On<Remove, Static>, mut commands: Commands) {
+    commands
+        .entity(remove.entity)
+        .try_remove::<StaticMeshMoves>();
+}
+
+fn respawn_moved_static_meshes(
+    mut moved: Query<
+        (
+            Entity,
+            Ref<SteamAudioStaticMesh>,
+            Option<&mut StaticMeshMoves>,
+        ),
+        Changed<GlobalTransform>,
+    >,
+    statics: Query<Option<&InheritedSteamAudio>, With<Static>>,
+    parents: Query<&ChildOf>,
+    time: Res<Time<Real>>,
+    mut commands: Commands,
+) {
+    let now = time.elapsed();
+    for (entity, static_mesh, moves) in &mut moved {
+        if static_mesh.is_changed() {
+            // Spawned this frame, so it already uses the current transform
+            continue;
+        }
+        // Static meshes have their transform baked into their vertices, so they need to be rebuilt
+        commands
+            .entity(entity)
+            .try_insert(InSteamAudioMeshSpawnQueue);
+        let Some(mut moves) = moves else {
+            commands.entity(entity).try_insert(StaticMeshMoves {
+                window_start: now,
+                count: 1,
+                warned: false,
+            });
+            continue;
+        };
+        if now.saturating_sub(moves.window_start) > STATIC_MOVE_WINDOW {
+            moves.window_start = now;
+            moves.count = 0;
+            moves.warned = false;
+        }
+        moves.count += 1;
+        if moves.count >= STATIC_MOVES_BEFORE_WARNING && !moves.warned {
+            moves.warned = true;
+            // `Static` may be inherited from an ancestor, which is where it has to be removed
+            let owner = core::iter::once(entity)
+                .chain(parents.iter_ancestors(entity))
+                .find(|&candidate| {
+                    statics.get(candidate).is_ok_and(|inherited| {
+                        !inherited.is_some_and(|inherited| inherited.is_static)
+                    })
+                })
+                .unwrap_or(entity);
+            warn!(
+                "{entity}: Static Steam Audio mesh moved {} times within {STATIC_MOVE_WINDOW:?} and was respawned every time. \
+                Consider removing `Static` from {owner} so that the mesh is instanced and moved cheaply instead.",
+                moves.count
+            );
+        }
+    }
+}
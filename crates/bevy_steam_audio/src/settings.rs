@@ -1,30 +1,81 @@
-use std::time::Duration;
+use std::{num::NonZeroU32, time::Duration};
 
 use crate::{prelude::*, wrapper::AudionimbusCoordinateSystem};
+use bevy_seedling::prelude::Volume;
 
 pub(super) fn plugin(app: &mut App) {
     app.init_resource::<SteamAudioEnabled>()
         .init_resource::<SteamAudioQuality>()
+        .init_resource::<SteamAudioOutputMode>()
+        .init_resource::<SteamAudioSimulationThread>()
+        .init_resource::<SteamAudioSimulationBudget>()
+        .init_resource::<SteamAudioPauseMode>()
+        .init_resource::<SteamAudioLatencyMode>()
         .init_resource::<SteamAudioPathBakingSettings>();
 }
 
+/// Toggles for the individual parts of the simulation.
+///
+/// Disabled paths are faded out by the audio nodes and stop being simulated,
+/// so they can be compared against each other at runtime.
 #[derive(Debug, Clone, PartialEq, Reflect, Resource)]
 #[reflect(Resource)]
 pub struct SteamAudioEnabled {
-    pub enabled: bool,
-    pub reflection_and_pathing_simulation_timer: Option<Timer>,
+    /// Whether the direct path from sources to the listener is simulated and heard.
+    pub direct: bool,
+    /// Whether reflections of sources are simulated and heard.
+    pub reflections: bool,
+    /// Whether sound propagating from sources along baked paths is simulated and heard.
+    pub pathing: bool,
+    /// Whether the reverb of the listener's surroundings is simulated and heard.
+    pub reverb: bool,
+    /// How often reflections and pathing are simulated while they are enabled.
+    pub reflection_and_pathing_simulation_timer: Timer,
 }
 
+/// The quality of the Steam Audio simulation and effects.
+///
+/// Most fields are baked into the simulator and the audio nodes, so changing them at runtime recreates all of them.
+/// Fields that can change live without a rebuild say so in their documentation. See [`Self::requires_rebuild`].
+///
+/// Use [`SteamAudioQualityPreset`] for a set of sensible values.
+/// With the `serde` feature, this can be saved as part of your user settings, e.g. in RON:
+///
+/// ```ron
+/// (
+///     order: 2,
+///     frame_size: 1024,
+///     num_bounces: 4,
+///     irradiance_min_distance: 1.0,
+///     direct: (max_num_occlusion_samples: 16),
+///     reflections: (
+///         num_rays: 4096,
+///         num_diffuse_samples: 32,
+///         impulse_duration: (secs: 2, nanos: 0),
+///         max_num_sources: 8,
+///         num_threads: 2,
+///         kind: Convolution,
+///     ),
+///     pathing: (num_visibility_samples: 4),
+/// )
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq, Reflect, Resource)]
 #[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SteamAudioQuality {
+    /// The ambisonic order used for reflections and pathing.
     pub order: u32,
+    /// The number of samples processed at once by the Steam Audio nodes.
     pub frame_size: u32,
     /// The number of times each ray traced from the listener is reflected when it encounters a solid object.
     /// Increasing this value results in longer, more accurate reverb tails, at the cost of increased CPU usage during simulation.
+    ///
+    /// Can be changed live.
     pub num_bounces: u32,
 
     /// When calculating how much sound energy reaches a surface directly from a source, any source that is closer than [`Self::irradiance_min_distance`] to the surface is assumed to be at a distance of [`Self::irradiance_min_distance`], for the purposes of energy calculations.
+    ///
+    /// Can be changed live.
     pub irradiance_min_distance: f32,
 
     pub direct: SteamAudioDirectQuality,
@@ -32,12 +83,153 @@ pub struct SteamAudioQuality {
     pub pathing: SteamAudioPathingQuality,
 }
 
+/// How spatialized audio is rendered to the two output channels.
+///
+/// Changing this at runtime rebuilds the Steam Audio nodes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect, Resource)]
+#[reflect(Resource, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SteamAudioOutputMode {
+    /// Binaural rendering using an HRTF. Use this for headphones.
+    #[default]
+    Binaural,
+    /// Plain stereo panning. Use this for speakers.
+    Panning,
+}
+
+impl SteamAudioOutputMode {
+    pub(crate) fn is_binaural(self) -> bool {
+        self == Self::Binaural
+    }
+}
+
+/// How the Steam Audio nodes trade latency for efficiency.
+///
+/// The nodes buffer their input until a whole frame of [`SteamAudioQuality::frame_size`] samples is collected,
+/// which delays spatialized audio relative to the rest of the graph.
+/// The resulting latency is reported to the audio graph and stored in
+/// [`AudionimbusSimulator::latency_frames`](crate::simulation::AudionimbusSimulator::latency_frames).
+///
+/// Changing this at runtime recreates the simulator.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Resource)]
+#[reflect(Resource, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SteamAudioLatencyMode {
+    /// Always process frames of [`SteamAudioQuality::frame_size`] samples.
+    #[default]
+    FixedFrameSize,
+    /// Use the host's block size as the frame size, so no extra buffering is needed.
+    /// Falls back to [`SteamAudioQuality::frame_size`] if the block size is unknown.
+    ///
+    /// Small host blocks make the reflection simulation and convolution more expensive.
+    MatchHostBlockSize,
+}
+
+impl SteamAudioLatencyMode {
+    /// Returns the quality the simulator and nodes actually run with.
+    pub(crate) fn apply(
+        self,
+        mut quality: SteamAudioQuality,
+        max_block_frames: Option<NonZeroU32>,
+    ) -> SteamAudioQuality {
+        if self == Self::MatchHostBlockSize
+            && let Some(max_block_frames) = max_block_frames
+        {
+            quality.frame_size = max_block_frames.get();
+        }
+        quality
+    }
+}
+
+/// How Steam Audio reacts to [`Time<Virtual>`] being paused, e.g. by a pause menu.
+///
+/// Only the [`SteamAudioPool`](crate::nodes::SteamAudioPool) and [`SteamAudioReverbPool`](crate::nodes::SteamAudioReverbPool)
+/// are affected, so non-spatial audio such as UI sounds keeps playing.
+/// When the game resumes, the effects of all Steam Audio nodes are reset so no stale state is heard.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Reflect, Resource)]
+#[reflect(Resource, Default)]
+pub enum SteamAudioPauseMode {
+    /// Keep simulating while the game is paused.
+    #[default]
+    Ignore,
+    /// Freeze the simulation, so spatialized sounds keep playing with their last simulated values.
+    Freeze,
+    /// Freeze the simulation and change the volume of the spatialized pools.
+    Duck {
+        /// The volume of the spatialized pools while paused.
+        volume: Volume,
+    },
+    /// Freeze the simulation and pause all samples playing in the spatialized pools.
+    Pause,
+}
+
+/// Where the expensive reflection and pathing simulations run.
+///
+/// Changing this at runtime recreates the simulator.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Resource)]
+#[reflect(Resource, Default)]
+pub enum SteamAudioSimulationThread {
+    /// Run on a thread of Bevy's [`AsyncComputeTaskPool`], which is occupied for as long as the simulator exists.
+    #[default]
+    TaskPool,
+    /// Run on a dedicated OS thread owned by the simulator.
+    /// It is shut down when the simulator is recreated or the app exits.
+    /// If the thread cannot be spawned, the simulations run on the main thread instead.
+    Dedicated {
+        /// The scheduling priority of the thread.
+        priority: SteamAudioThreadPriority,
+    },
+}
+
+/// The scheduling priority of a [`SteamAudioSimulationThread::Dedicated`] thread.
+///
+/// Raising the priority above normal may require elevated privileges on some platforms.
+/// If the priority cannot be set, a warning is logged and the thread keeps the default priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub enum SteamAudioThreadPriority {
+    /// Keep the priority the OS assigns to new threads.
+    #[default]
+    Normal,
+    /// The lowest priority, for when the simulation should only use otherwise idle CPU time.
+    Min,
+    /// The highest priority.
+    Max,
+    /// A platform-independent priority between 0 (lowest) and 99 (highest).
+    Value(u8),
+}
+
+impl SteamAudioThreadPriority {
+    /// Applies the priority to the calling thread.
+    pub(crate) fn apply_to_current_thread(self) {
+        use thread_priority::{ThreadPriority, ThreadPriorityValue};
+
+        let priority = match self {
+            Self::Normal => return,
+            Self::Min => ThreadPriority::Min,
+            Self::Max => ThreadPriority::Max,
+            Self::Value(value) => match ThreadPriorityValue::try_from(value.min(99)) {
+                Ok(value) => ThreadPriority::Crossplatform(value),
+                Err(err) => {
+                    warn!("Invalid Steam Audio simulation thread priority {value}: {err}");
+                    return;
+                }
+            },
+        };
+        if let Err(err) = thread_priority::set_current_thread_priority(priority) {
+            warn!("Failed to set Steam Audio simulation thread priority to {self:?}: {err:?}");
+        }
+    }
+}
+
 /// Settings used for direct path simulation.
 #[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SteamAudioDirectQuality {
     /// The maximum number of point samples to consider when calculating occlusion using the volumetric occlusion algorithm.
     /// Different sources can use different numbers of samples, and the number of samples can change between simulation runs, but this is the maximum value.
     /// Increasing this value results in smoother occlusion transitions, at the cost of increased CPU usage.
+    ///
+    /// Can be lowered live. Raising it above the value the simulator was created with recreates it.
     pub max_num_occlusion_samples: u32,
 }
 
@@ -58,6 +250,7 @@ impl Default for SteamAudioDirectQuality {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Settings used for pathing simulation.
 pub struct SteamAudioPathingQuality {
     /// The number of point samples to consider when calculating probe-to-probe visibility for pathing simulations.
@@ -83,10 +276,13 @@ impl Default for SteamAudioPathingQuality {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Settings used for reflections simulation.
 pub struct SteamAudioReflectionsQuality {
     /// The maximum number of rays to trace from the listener when simulating reflections.
     /// Increasing this value results in more accurate reflections, at the cost of increased CPU usage.
+    ///
+    /// Can be lowered live. Raising it above the value the simulator was created with recreates it.
     pub num_rays: u32,
     /// The number of directions to sample when generating diffusely reflected rays.
     /// Increasing this value may increase the accuracy of diffuse reflections.
@@ -98,10 +294,12 @@ pub struct SteamAudioReflectionsQuality {
     pub max_num_sources: u32,
     /// The number of threads used for real-time reflection simulations.
     pub num_threads: u32,
+    /// The kind of reverb the reflections are rendered with.
     pub kind: SteamAudioReflectionKind,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Settings used for reflections simulation.
 pub enum SteamAudioReflectionKind {
     /// Multi-channel convolution reverb.
@@ -205,6 +403,190 @@ impl SteamAudioQuality {
     pub fn num_channels(self) -> u32 {
         order_to_num_channels(self.order)
     }
+
+    /// Returns `true` if going from `self` to `new` requires recreating the simulator and all Steam Audio nodes.
+    pub fn requires_rebuild(&self, new: &Self) -> bool {
+        self.order != new.order
+            || self.frame_size != new.frame_size
+            || new.direct.max_num_occlusion_samples > self.direct.max_num_occlusion_samples
+            || new.reflections.num_rays > self.reflections.num_rays
+            || self.reflections.num_diffuse_samples != new.reflections.num_diffuse_samples
+            || self.reflections.impulse_duration != new.reflections.impulse_duration
+            || self.reflections.max_num_sources != new.reflections.max_num_sources
+            || self.reflections.num_threads != new.reflections.num_threads
+            || self.reflections.kind != new.reflections.kind
+            || self.pathing != new.pathing
+    }
+
+    /// Returns `true` if sources created for a simulator with `self` cannot be migrated to a simulator with `new`.
+    pub(crate) fn requires_new_sources(&self, new: &Self) -> bool {
+        self.order != new.order
+            || self.frame_size != new.frame_size
+            || self.reflections.impulse_duration != new.reflections.impulse_duration
+            || self.reflections.kind != new.reflections.kind
+    }
+
+    pub(crate) fn reflection_effect_settings(
+        self,
+        sampling_rate: u32,
+    ) -> audionimbus::ReflectionEffectSettings {
+        let impulse_response_size = self.impulse_response_size(sampling_rate);
+        let num_channels = self.num_channels();
+        match self.reflections.kind {
+            SteamAudioReflectionKind::Convolution => {
+                audionimbus::ReflectionEffectSettings::Convolution {
+                    impulse_response_size,
+                    num_channels,
+                }
+            }
+            SteamAudioReflectionKind::Parametric => {
+                audionimbus::ReflectionEffectSettings::Parametric {
+                    impulse_response_size,
+                    num_channels,
+                }
+            }
+            SteamAudioReflectionKind::Hybrid => audionimbus::ReflectionEffectSettings::Hybrid {
+                impulse_response_size,
+                num_channels,
+            },
+        }
+    }
+}
+
+impl SteamAudioReflectionKind {
+    /// Uses Steam Audio's default reverb scale and hybrid transition.
+    pub(crate) fn simulation_parameters(self) -> audionimbus::ReflectionsSimulationParameters {
+        match self {
+            SteamAudioReflectionKind::Convolution => {
+                audionimbus::ReflectionsSimulationParameters::Convolution {
+                    baked_data_identifier: None,
+                }
+            }
+            SteamAudioReflectionKind::Parametric => {
+                audionimbus::ReflectionsSimulationParameters::Parametric {
+                    reverb_scale: [1.0; 3],
+                    baked_data_identifier: None,
+                }
+            }
+            SteamAudioReflectionKind::Hybrid => {
+                audionimbus::ReflectionsSimulationParameters::Hybrid {
+                    reverb_scale: [1.0; 3],
+                    hybrid_reverb_transition_time: 1.0,
+                    hybrid_reverb_overlap_percent: 0.25,
+                    baked_data_identifier: None,
+                }
+            }
+        }
+    }
+}
+
+/// Named sets of [`SteamAudioQuality`] values, e.g. for a settings menu.
+///
+/// With the `serde` feature, this can be saved as part of your user settings, e.g. as `Medium` in RON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SteamAudioQualityPreset {
+    /// No reflection, pathing or reverb simulation. Direct sound is still spatialized at [`Self::Low`] quality.
+    Off,
+    /// Parametric reverb and few rays, for weak hardware.
+    Low,
+    /// The default quality.
+    #[default]
+    Medium,
+    /// More rays, bounces and sources than [`Self::Medium`].
+    High,
+    /// Third order ambisonics and long reverb tails, for when CPU time is not a concern.
+    Ultra,
+}
+
+impl SteamAudioQualityPreset {
+    /// The quality settings of this preset.
+    pub fn quality(self) -> SteamAudioQuality {
+        match self {
+            Self::Off | Self::Low => SteamAudioQuality {
+                order: 1,
+                frame_size: 1024,
+                num_bounces: 2,
+                irradiance_min_distance: 1.0,
+                direct: SteamAudioDirectQuality {
+                    max_num_occlusion_samples: 4,
+                },
+                reflections: SteamAudioReflectionsQuality {
+                    num_rays: 1024,
+                    num_diffuse_samples: 16,
+                    impulse_duration: Duration::from_secs_f32(1.0),
+                    max_num_sources: 4,
+                    num_threads: 1,
+                    kind: SteamAudioReflectionKind::Parametric,
+                },
+                pathing: SteamAudioPathingQuality {
+                    num_visibility_samples: 2,
+                },
+            },
+            Self::Medium => SteamAudioQuality::default(),
+            Self::High => SteamAudioQuality {
+                order: 2,
+                frame_size: 1024,
+                num_bounces: 8,
+                irradiance_min_distance: 1.0,
+                direct: SteamAudioDirectQuality {
+                    max_num_occlusion_samples: 32,
+                },
+                reflections: SteamAudioReflectionsQuality {
+                    num_rays: 8192,
+                    num_diffuse_samples: 64,
+                    impulse_duration: Duration::from_secs_f32(2.0),
+                    max_num_sources: 16,
+                    num_threads: 4,
+                    kind: SteamAudioReflectionKind::Convolution,
+                },
+                pathing: SteamAudioPathingQuality {
+                    num_visibility_samples: 8,
+                },
+            },
+            Self::Ultra => SteamAudioQuality {
+                order: 3,
+                frame_size: 1024,
+                num_bounces: 16,
+                irradiance_min_distance: 1.0,
+                direct: SteamAudioDirectQuality {
+                    max_num_occlusion_samples: 64,
+                },
+                reflections: SteamAudioReflectionsQuality {
+                    num_rays: 16384,
+                    num_diffuse_samples: 128,
+                    impulse_duration: Duration::from_secs_f32(3.0),
+                    max_num_sources: 32,
+                    num_threads: 4,
+                    kind: SteamAudioReflectionKind::Convolution,
+                },
+                pathing: SteamAudioPathingQuality {
+                    num_visibility_samples: 16,
+                },
+            },
+        }
+    }
+
+    /// Applies this preset.
+    ///
+    /// The resources are only marked as changed if they actually differ,
+    /// and only fields that are not live recreate the simulator.
+    pub fn apply(
+        self,
+        quality: &mut ResMut<SteamAudioQuality>,
+        enabled: &mut ResMut<SteamAudioEnabled>,
+    ) {
+        quality.set_if_neq(self.quality());
+        let simulating = enabled.reflections || enabled.pathing || enabled.reverb;
+        // Leave the toggles alone unless switching from or to `Off`
+        if (self == Self::Off) == simulating {
+            let simulate = self != Self::Off;
+            enabled.reflections = simulate;
+            enabled.pathing = simulate;
+            enabled.reverb = simulate;
+        }
+    }
 }
 
 impl Default for SteamAudioQuality {
@@ -224,11 +606,39 @@ impl Default for SteamAudioQuality {
 impl Default for SteamAudioEnabled {
     fn default() -> Self {
         Self {
-            enabled: true,
-            reflection_and_pathing_simulation_timer: Some(Timer::from_seconds(
-                0.1,
-                TimerMode::Once,
-            )),
+            direct: true,
+            reflections: true,
+            pathing: true,
+            reverb: true,
+            reflection_and_pathing_simulation_timer: Timer::from_seconds(0.1, TimerMode::Once),
+        }
+    }
+}
+
+/// A CPU time budget for the reflection and pathing simulation.
+///
+/// [`SteamAudioEnabled::reflection_and_pathing_simulation_timer`] sets the shortest interval between simulations.
+/// When the measured cost of a simulation would exceed the budget at that rate, simulations are spaced further apart.
+/// If that would push the interval past [`Self::max_interval`], the number of rays and bounces is scaled down as well.
+/// See [`SteamAudioSimulationStats`](crate::simulation::SteamAudioSimulationStats) for the current state.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Resource)]
+#[reflect(Resource)]
+pub struct SteamAudioSimulationBudget {
+    /// How much CPU time reflections and pathing may use per second.
+    /// Set to `None` to always simulate at the rate of the timer and at full quality.
+    pub cpu_time_per_second: Option<Duration>,
+    /// The longest interval between simulations before rays and bounces are scaled down.
+    pub max_interval: Duration,
+    /// The lowest fraction of [`SteamAudioReflectionsQuality::num_rays`] and [`SteamAudioQuality::num_bounces`] to scale down to.
+    pub min_quality_scale: f32,
+}
+
+impl Default for SteamAudioSimulationBudget {
+    fn default() -> Self {
+        Self {
+            cpu_time_per_second: Some(Duration::from_millis(250)),
+            max_interval: Duration::from_millis(500),
+            min_quality_scale: 0.25,
         }
     }
 }
@@ -261,3 +671,52 @@ impl Default for SteamAudioPathBakingSettings {
 
 #[derive(Debug, Clone, PartialEq, Resource)]
 pub struct SteamAudioHrtf(pub audionimbus::Hrtf);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switching_reflection_kind_through_preset_requires_rebuild() {
+        let low = SteamAudioQualityPreset::Low.quality();
+        let medium = SteamAudioQualityPreset::Medium.quality();
+        assert_ne!(low.reflections.kind, medium.reflections.kind);
+        assert!(medium.requires_rebuild(&low));
+        assert!(low.requires_rebuild(&medium));
+        assert!(medium.requires_new_sources(&low));
+
+        // The kind alone is enough to require a rebuild
+        let mut parametric = medium;
+        parametric.reflections.kind = SteamAudioReflectionKind::Parametric;
+        assert!(medium.requires_rebuild(&parametric));
+        assert!(!medium.requires_rebuild(&medium));
+    }
+
+    #[test]
+    fn reflection_effect_settings_follow_reflection_kind() {
+        let mut quality = SteamAudioQualityPreset::Low.quality();
+        for kind in [
+            SteamAudioReflectionKind::Convolution,
+            SteamAudioReflectionKind::Parametric,
+            SteamAudioReflectionKind::Hybrid,
+        ] {
+            quality.reflections.kind = kind;
+            let settings = quality.reflection_effect_settings(48_000);
+            let matches_kind = match kind {
+                SteamAudioReflectionKind::Convolution => matches!(
+                    settings,
+                    audionimbus::ReflectionEffectSettings::Convolution { .. }
+                ),
+                SteamAudioReflectionKind::Parametric => matches!(
+                    settings,
+                    audionimbus::ReflectionEffectSettings::Parametric { .. }
+                ),
+                SteamAudioReflectionKind::Hybrid => matches!(
+                    settings,
+                    audionimbus::ReflectionEffectSettings::Hybrid { .. }
+                ),
+            };
+            assert!(matches_kind, "{kind:?}");
+        }
+    }
+}
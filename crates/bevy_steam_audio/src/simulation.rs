@@ -2,49 +2,74 @@ use std::{
     num::NonZeroU32,
     sync::{
         Arc, RwLock,
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
     },
+    time::{Duration, Instant},
 };
 
 use crate::{
     STEAM_AUDIO_CONTEXT, SteamAudioListener,
+    models::{
+        SourceAirAbsorptionModel, SourceAttenuationModel, SteamAudioAirAbsorption,
+        SteamAudioDistanceAttenuation,
+    },
     nodes::{
-        SteamAudioNodeConfig, SteamAudioReverbNodeConfig, encoder::SteamAudioNode,
-        reverb::SteamAudioReverbNode,
+        FixedProcessBlock, SteamAudioNodeConfig, SteamAudioReverbNodeConfig,
+        encoder::{SteamAudioNode, SteamAudioNodeEffects},
+        reverb::{SteamAudioReverbNode, SteamAudioReverbNodeEffects},
     },
+    pause,
     prelude::*,
     probes::SteamAudioProbeBatch,
     scene::SteamAudioRootScene,
     settings::{
-        SteamAudioEnabled, SteamAudioHrtf, SteamAudioPathBakingSettings, SteamAudioQuality,
+        SteamAudioEnabled, SteamAudioHrtf, SteamAudioLatencyMode, SteamAudioOutputMode,
+        SteamAudioPathBakingSettings, SteamAudioQuality, SteamAudioSimulationBudget,
+        SteamAudioSimulationThread,
+    },
+    sources::{
+        AudionimbusSource, InactiveSource, ListenerSource, SourcesToRemove, SteamAudioSource,
     },
-    sources::{AudionimbusSource, ListenerSource, SourcesToRemove},
 };
 
+use bevy_ecs::entity_disabling::Disabled;
 use bevy_seedling::{
-    context::{StreamRestartEvent, StreamStartEvent},
+    context::{SeedlingContextWrapper as _, StreamRestartEvent, StreamStartEvent},
     prelude::*,
 };
+use firewheel::event::NodeEventType;
 
 use crate::wrapper::*;
 
 pub(super) fn plugin(app: &mut App) {
+    let schedule = SteamAudioSchedule::from_app(app);
     app.add_systems(
-        PostUpdate,
+        schedule,
         (recreate_simulator_on_settings_change)
             .in_set(SteamAudioSystems::CreateSimulator)
             .run_if(resource_exists::<AudionimbusSimulator>),
     );
     app.add_systems(
-        PostUpdate,
+        schedule,
         update_simulation
             .in_set(SteamAudioSystems::RunSimulator)
             .run_if(
                 resource_exists::<AsyncSimulationSynchronization>
-                    .and(resource_exists::<AudionimbusSimulator>),
+                    .and(resource_exists::<AudionimbusSimulator>)
+                    .and(not(pause::simulation_frozen)),
             ),
     );
+    app.add_systems(
+        Last,
+        shut_down_simulation
+            .run_if(on_message::<AppExit>.and(resource_exists::<AsyncSimulationSynchronization>)),
+    );
+    app.init_resource::<SteamAudioSimulationStats>()
+        .init_resource::<SteamAudioSourcePool>()
+        .init_resource::<RetiredSourceModels>();
     app.add_observer(create_simulator)
+        .add_observer(retire_attenuation_model)
+        .add_observer(retire_air_absorption_model)
         .add_observer(create_simulator_on_stream_start)
         .add_observer(create_simulator_on_stream_restart);
 }
@@ -54,8 +79,196 @@ pub struct SteamAudioReady;
 
 #[derive(Resource)]
 struct AsyncSimulationSynchronization {
-    sender: crossbeam_channel::Sender<()>,
+    /// Sends which simulations to run next to the simulation thread.
+    /// Dropping this stops the thread once its current simulation is done, without waiting for it.
+    /// If `None`, simulations run synchronously through `run`.
+    sender: Option<crossbeam_channel::Sender<audionimbus::SimulationFlags>>,
+    /// Runs the given simulations on the calling thread.
+    run: Arc<dyn Fn(audionimbus::SimulationFlags) + Send + Sync>,
     complete: Arc<AtomicBool>,
+    /// How long the last reflection and pathing simulation took in nanoseconds, or 0 if it was already recorded.
+    duration_nanos: Arc<AtomicU64>,
+}
+
+/// Measurements of the reflection and pathing simulation,
+/// used to stay within the [`SteamAudioSimulationBudget`].
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Resource)]
+#[reflect(Resource)]
+pub struct SteamAudioSimulationStats {
+    /// How long the last simulation took.
+    pub last_duration: Duration,
+    /// A moving average of how long simulations take.
+    pub average_duration: Duration,
+    /// The current interval between simulations.
+    pub interval: Duration,
+    /// The fraction of rays and bounces currently simulated.
+    pub quality_scale: f32,
+    /// When the last simulation was started, relative to app startup.
+    last_start: Duration,
+}
+
+impl Default for SteamAudioSimulationStats {
+    fn default() -> Self {
+        Self {
+            last_duration: Duration::ZERO,
+            average_duration: Duration::ZERO,
+            interval: Duration::ZERO,
+            quality_scale: 1.0,
+            last_start: Duration::ZERO,
+        }
+    }
+}
+
+impl SteamAudioSimulationStats {
+    fn record(
+        &mut self,
+        duration: Duration,
+        budget: &SteamAudioSimulationBudget,
+        min_interval: Duration,
+    ) {
+        self.last_duration = duration;
+        self.average_duration = if self.average_duration.is_zero() {
+            duration
+        } else {
+            self.average_duration.mul_f32(0.8) + duration.mul_f32(0.2)
+        };
+
+        let Some(cpu_time) = budget
+            .cpu_time_per_second
+            .filter(|cpu_time| !cpu_time.is_zero())
+        else {
+            self.interval = min_interval;
+            self.quality_scale = 1.0;
+            return;
+        };
+        // Simulating for `average_duration` every `needed_interval` uses exactly `cpu_time` per second.
+        let needed_interval = self.average_duration.div_f32(cpu_time.as_secs_f32());
+        if needed_interval > budget.max_interval {
+            self.quality_scale = (self.quality_scale * 0.8).max(budget.min_quality_scale);
+        } else if needed_interval < budget.max_interval / 2 {
+            self.quality_scale = (self.quality_scale * 1.1).min(1.0);
+        }
+        // If even the lowest quality is over budget, we keep degrading the update rate instead.
+        self.interval = needed_interval.max(min_interval);
+    }
+
+    fn scale(&self, inputs: &mut audionimbus::SimulationSharedInputs) {
+        inputs.num_rays = ((inputs.num_rays as f32 * self.quality_scale) as u32).max(1);
+        inputs.num_bounces =
+            ((inputs.num_bounces as f32 * self.quality_scale).round() as u32).max(1);
+    }
+}
+
+/// Recycles the Steam Audio sources of pooled samplers, as creating them is expensive.
+///
+/// Released sources are removed from the simulator and only become free again after the next commit,
+/// so a source is never removed and re-added within the same commit.
+#[derive(Resource, Default)]
+pub struct SteamAudioSourcePool {
+    free: Vec<audionimbus::Source>,
+    pending: Vec<audionimbus::Source>,
+    active: usize,
+}
+
+impl SteamAudioSourcePool {
+    /// The number of sources currently used by sample players.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// The number of sources waiting to be reused.
+    pub fn free(&self) -> usize {
+        self.free.len() + self.pending.len()
+    }
+
+    pub(crate) fn take(
+        &mut self,
+        simulator: &audionimbus::Simulator<
+            audionimbus::Direct,
+            audionimbus::Reflections,
+            audionimbus::Pathing,
+        >,
+    ) -> Result<audionimbus::Source, audionimbus::SteamAudioError> {
+        let source = match self.free.pop() {
+            Some(source) => source,
+            None => Self::create(simulator)?,
+        };
+        self.active += 1;
+        Ok(source)
+    }
+
+    fn create(
+        simulator: &audionimbus::Simulator<
+            audionimbus::Direct,
+            audionimbus::Reflections,
+            audionimbus::Pathing,
+        >,
+    ) -> Result<audionimbus::Source, audionimbus::SteamAudioError> {
+        audionimbus::Source::try_new(
+            simulator,
+            &audionimbus::SourceSettings {
+                flags: audionimbus::SimulationFlags::all(),
+            },
+        )
+    }
+
+    pub(crate) fn release(&mut self, source: audionimbus::Source) {
+        self.active = self.active.saturating_sub(1);
+        self.pending.push(source);
+    }
+
+    /// Called after the simulator committed, making all released sources reusable.
+    fn committed(&mut self) {
+        self.free.append(&mut self.pending);
+    }
+
+    /// Drops all sources waiting to be reused, as they were created for a previous simulator.
+    fn clear(&mut self) {
+        self.free.clear();
+        self.pending.clear();
+    }
+}
+
+/// Models no source uses anymore.
+/// They are kept alive until the simulator is committed, as it may call into them until then.
+#[derive(Resource, Default)]
+struct RetiredSourceModels {
+    attenuation: Vec<SourceAttenuationModel>,
+    air_absorption: Vec<SourceAirAbsorptionModel>,
+}
+
+impl RetiredSourceModels {
+    fn clear(&mut self) {
+        self.attenuation.clear();
+        self.air_absorption.clear();
+    }
+}
+
+// `Replace` also covers the source being despawned.
+fn retire_attenuation_model(
+    replace: On<Replace, SourceAttenuationModel>,
+    models: Query<&SourceAttenuationModel, Allow<Disabled>>,
+    mut retired: ResMut<RetiredSourceModels>,
+) -> Result {
+    retired
+        .attenuation
+        .push(models.get(replace.entity)?.clone());
+    Ok(())
+}
+
+fn retire_air_absorption_model(
+    replace: On<Replace, SourceAirAbsorptionModel>,
+    models: Query<&SourceAirAbsorptionModel, Allow<Disabled>>,
+    mut retired: ResMut<RetiredSourceModels>,
+) -> Result {
+    retired
+        .air_absorption
+        .push(models.get(replace.entity)?.clone());
+    Ok(())
+}
+
+fn shut_down_simulation(mut commands: Commands) {
+    commands.remove_resource::<AsyncSimulationSynchronization>();
 }
 
 #[derive(Resource)]
@@ -70,6 +283,12 @@ pub struct AudionimbusSimulator {
         >,
     >,
     pub sampling_rate: NonZeroU32,
+    /// The host's maximum block size when the simulator was created, if known.
+    pub max_block_frames: Option<NonZeroU32>,
+    /// The quality the simulator was created with, after applying the [`SteamAudioLatencyMode`].
+    pub quality: SteamAudioQuality,
+    /// How many frames the Steam Audio nodes delay their output by.
+    pub latency_frames: u32,
 }
 impl AudionimbusSimulator {
     /// Used to force consumers to only ever use `ResMut` and not `Res`,
@@ -91,43 +310,105 @@ impl AudionimbusSimulator {
     }
 }
 
+/// The quality and latency that newly added nodes are created with.
+pub(crate) fn node_quality(world: &World) -> (SteamAudioQuality, u32) {
+    match world.get_resource::<AudionimbusSimulator>() {
+        Some(simulator) => (simulator.quality, simulator.latency_frames),
+        None => {
+            let quality = world
+                .get_resource::<SteamAudioQuality>()
+                .copied()
+                .unwrap_or_default();
+            (quality, quality.frame_size)
+        }
+    }
+}
+
+fn host_max_block_frames(context: &mut AudioContext) -> Option<NonZeroU32> {
+    context.with(|context| context.stream_info().map(|info| info.max_block_frames))
+}
+
 #[derive(Event)]
 struct CreateSimulator {
     sampling_rate: NonZeroU32,
+    max_block_frames: Option<NonZeroU32>,
+    /// Keep the existing nodes running and only send them effects built with the new HRTF,
+    /// instead of rebuilding them with new configurations.
+    keep_nodes: bool,
 }
 
-fn create_simulator_on_stream_start(stream_start: On<StreamStartEvent>, mut commands: Commands) {
+fn create_simulator_on_stream_start(
+    stream_start: On<StreamStartEvent>,
+    mut context: ResMut<AudioContext>,
+    mut commands: Commands,
+) {
     commands.trigger(CreateSimulator {
         sampling_rate: stream_start.sample_rate,
+        max_block_frames: host_max_block_frames(&mut context),
+        keep_nodes: false,
     });
 }
 
 fn create_simulator_on_stream_restart(
     stream_restart: On<StreamRestartEvent>,
+    simulator: Option<Res<AudionimbusSimulator>>,
+    quality: Res<SteamAudioQuality>,
+    latency_mode: Res<SteamAudioLatencyMode>,
+    mut context: ResMut<AudioContext>,
     mut commands: Commands,
 ) {
+    let max_block_frames = host_max_block_frames(&mut context);
+    // The nodes' fixed blocks can only be kept if the frame size stays the same
+    let frame_size_unchanged = simulator.as_ref().is_some_and(|simulator| {
+        latency_mode.apply(*quality, max_block_frames).frame_size == simulator.quality.frame_size
+    });
+    if let Some(simulator) = simulator.as_ref()
+        && simulator.sampling_rate == stream_restart.current_rate
+        && frame_size_unchanged
+    {
+        // The simulator, HRTF, sources and probes are still valid for the new stream,
+        // and the nodes keep their effects, so reverb tails and playing sources carry over.
+        return;
+    }
     commands.trigger(CreateSimulator {
         sampling_rate: stream_restart.current_rate,
+        max_block_frames,
+        keep_nodes: frame_size_unchanged,
     });
 }
 
 fn recreate_simulator_on_settings_change(
     quality: Res<SteamAudioQuality>,
+    output: Res<SteamAudioOutputMode>,
+    simulation_thread: Res<SteamAudioSimulationThread>,
+    latency_mode: Res<SteamAudioLatencyMode>,
     simulator: ResMut<AudionimbusSimulator>,
     mut commands: Commands,
-    mut prev_quality: Local<Option<SteamAudioQuality>>,
+    mut initialized: Local<bool>,
 ) {
-    let Some(prev_quality) = *prev_quality else {
-        *prev_quality = Some(*quality);
+    if !*initialized {
+        // Everything counts as changed on the first run
+        *initialized = true;
         return;
-    };
+    }
 
-    if !quality.is_changed() && prev_quality == *quality {
+    // Live fields are picked up by `update_simulation` without a rebuild
+    let quality_requires_rebuild = quality.is_changed()
+        && simulator
+            .quality
+            .requires_rebuild(&latency_mode.apply(*quality, simulator.max_block_frames));
+    if !quality_requires_rebuild
+        && !output.is_changed()
+        && !simulation_thread.is_changed()
+        && !latency_mode.is_changed()
+    {
         return;
     }
 
     commands.trigger(CreateSimulator {
         sampling_rate: simulator.sampling_rate,
+        max_block_frames: simulator.max_block_frames,
+        keep_nodes: false,
     });
 }
 
@@ -135,42 +416,105 @@ fn create_simulator(
     create: On<CreateSimulator>,
     mut commands: Commands,
     quality: Res<SteamAudioQuality>,
+    output: Res<SteamAudioOutputMode>,
+    simulation_thread: Res<SteamAudioSimulationThread>,
+    latency_mode: Res<SteamAudioLatencyMode>,
     root: ResMut<SteamAudioRootScene>,
-    sources: Query<&AudionimbusSource>,
+    mut sources: Query<(
+        Entity,
+        &mut AudionimbusSource,
+        &SampleEffects,
+        Has<InactiveSource>,
+    )>,
+    mut source_pool: ResMut<SteamAudioSourcePool>,
     probe_batch: Option<Res<SteamAudioProbeBatch>>,
-    mut nodes: Query<&mut SteamAudioNodeConfig>,
-    mut reverb_nodes: Query<&mut SteamAudioReverbNodeConfig>,
-) -> Result {
+    previous_simulator: Option<Res<AudionimbusSimulator>>,
+    previous_hrtf: Option<Res<SteamAudioHrtf>>,
+    previous_listener_source: Option<Res<ListenerSource>>,
+    mut nodes: Query<(Entity, &mut SteamAudioNodeConfig, &mut AudioEvents)>,
+    mut reverb_nodes: Query<
+        (Entity, &mut SteamAudioReverbNodeConfig, &mut AudioEvents),
+        Without<SteamAudioNodeConfig>,
+    >,
+) {
+    let quality = latency_mode.apply(*quality, create.max_block_frames);
+    let latency_frames =
+        FixedProcessBlock::latency_frames(quality.frame_size, create.max_block_frames);
     let settings = audionimbus::AudioSettings {
         sampling_rate: create.sampling_rate.into(),
         frame_size: quality.frame_size,
     };
-    let hrtf = audionimbus::Hrtf::try_new(
-        &STEAM_AUDIO_CONTEXT,
-        &settings,
-        &audionimbus::HrtfSettings {
-            volume_normalization: audionimbus::VolumeNormalization::RootMeanSquared,
-            ..default()
+    // The HRTF only depends on the audio settings, so it can be reused as long as those stay the same.
+    let reusable_hrtf = previous_simulator
+        .as_ref()
+        .filter(|simulator| {
+            simulator.sampling_rate == create.sampling_rate
+                && simulator.quality.frame_size == quality.frame_size
+        })
+        .and(previous_hrtf)
+        .map(|hrtf| hrtf.0.clone());
+    let hrtf = match reusable_hrtf {
+        Some(hrtf) => hrtf,
+        None => match audionimbus::Hrtf::try_new(
+            &STEAM_AUDIO_CONTEXT,
+            &settings,
+            &audionimbus::HrtfSettings {
+                volume_normalization: audionimbus::VolumeNormalization::RootMeanSquared,
+                ..default()
+            },
+        ) {
+            Ok(hrtf) => hrtf,
+            Err(err) => {
+                commands.trigger(SteamAudioError::Simulator { error: err });
+                return;
+            }
         },
-    )
-    .unwrap();
-    for mut node_config in nodes.iter_mut() {
-        *node_config = SteamAudioNodeConfig {
-            quality: *quality,
+    };
+    for (entity, mut node_config, mut events) in nodes.iter_mut() {
+        let new_config = SteamAudioNodeConfig {
+            quality,
             hrtf: Some(hrtf.clone()),
+            output: *output,
+            latency_frames,
+        };
+        if create.keep_nodes {
+            // Changing the configuration would rebuild the node, cutting off its audio.
+            // If the new effects cannot be built, the node keeps its current ones.
+            match SteamAudioNodeEffects::try_new(entity, &new_config, create.sampling_rate.get()) {
+                Ok(effects) => events.push(NodeEventType::custom(effects)),
+                Err(error) => commands.trigger(error),
+            }
+            *node_config.bypass_change_detection() = new_config;
+        } else {
+            *node_config = new_config;
         }
     }
-    for mut reverb_node_config in reverb_nodes.iter_mut() {
-        *reverb_node_config = SteamAudioReverbNodeConfig {
-            quality: *quality,
+    for (entity, mut reverb_node_config, mut events) in reverb_nodes.iter_mut() {
+        let new_config = SteamAudioReverbNodeConfig {
+            quality,
             hrtf: Some(hrtf.clone()),
+            output: *output,
+            latency_frames,
+        };
+        if create.keep_nodes {
+            match SteamAudioReverbNodeEffects::try_new(
+                entity,
+                &new_config,
+                create.sampling_rate.get(),
+            ) {
+                Ok(effects) => events.push(NodeEventType::custom(effects)),
+                Err(error) => commands.trigger(error),
+            }
+            *reverb_node_config.bypass_change_detection() = new_config;
+        } else {
+            *reverb_node_config = new_config;
         }
     }
     commands.insert_resource(SteamAudioHrtf(hrtf));
     // All sources to be removed are already removed by despawning the old simulator
     commands.insert_resource(SourcesToRemove::default());
 
-    let mut simulator = audionimbus::Simulator::builder(
+    let simulator = audionimbus::Simulator::builder(
         audionimbus::SceneParams::Default,
         create.sampling_rate.into(),
         quality.frame_size,
@@ -178,63 +522,157 @@ fn create_simulator(
     .with_direct(quality.direct.into())
     .with_reflections(quality.reflections.to_audionimbus(quality.order))
     .with_pathing(quality.pathing.into())
-    .try_build(&STEAM_AUDIO_CONTEXT)?;
+    .try_build(&STEAM_AUDIO_CONTEXT);
+    let mut simulator = match simulator {
+        Ok(simulator) => simulator,
+        Err(err) => {
+            commands.trigger(SteamAudioError::Simulator { error: err });
+            return;
+        }
+    };
     simulator.set_scene(&root);
 
-    let listener_source = audionimbus::Source::try_new(
-        &simulator,
-        &audionimbus::SourceSettings {
-            flags: audionimbus::SimulationFlags::REFLECTIONS,
-        },
-    )?;
+    // Sources are sized for the audio settings and reflections of their simulator,
+    // so they can only be migrated to the new one if those stay the same.
+    let reuse_sources = previous_simulator.as_ref().is_some_and(|previous| {
+        previous.sampling_rate == create.sampling_rate
+            && !previous.quality.requires_new_sources(&quality)
+    });
+
+    // Existing sources are migrated to the new simulator so that they keep playing.
+    let listener_source = match previous_listener_source.filter(|_| reuse_sources) {
+        Some(listener_source) => Ok(listener_source.0.clone()),
+        None => audionimbus::Source::try_new(
+            &simulator,
+            &audionimbus::SourceSettings {
+                flags: audionimbus::SimulationFlags::REFLECTIONS,
+            },
+        ),
+    };
+    let listener_source = match listener_source {
+        Ok(source) => source,
+        Err(err) => {
+            commands.trigger(SteamAudioError::Simulator { error: err });
+            return;
+        }
+    };
     simulator.add_source(&listener_source);
 
-    for source in &sources {
-        simulator.add_source(source);
+    for (entity, mut source, effects, inactive) in &mut sources {
+        if !reuse_sources {
+            match SteamAudioSourcePool::create(&simulator) {
+                Ok(new_source) => {
+                    // Mutated in place, as replacing the component would release the old source into the pool
+                    source.0 = new_source;
+                    if let Ok((_, _, mut events)) = nodes.get_effect_mut(effects) {
+                        events.push(NodeEventType::custom(Some(source.0.clone())));
+                    }
+                }
+                Err(error) => {
+                    commands.trigger(SteamAudioError::SourceCreation { entity, error });
+                    commands.entity(entity).try_remove::<AudionimbusSource>();
+                    continue;
+                }
+            }
+        }
+        if !inactive {
+            simulator.add_source(&source);
+        }
     }
     if let Some(probe_batch) = probe_batch {
         simulator.add_probe_batch(&probe_batch);
     }
 
     simulator.commit();
+    // Released sources were not added to the new simulator in the first place
+    source_pool.committed();
+    if !reuse_sources {
+        // Deferred, so that sources released by the removals above are dropped as well
+        commands.queue(|world: &mut World| {
+            world.resource_mut::<SteamAudioSourcePool>().clear();
+        });
+    }
 
     let simulator = Arc::new(RwLock::new(simulator.clone()));
     commands.insert_resource(ListenerSource(listener_source));
     commands.insert_resource(AudionimbusSimulator {
         simulator: simulator.clone(),
         sampling_rate: create.sampling_rate,
+        max_block_frames: create.max_block_frames,
+        quality,
+        latency_frames,
     });
 
     let simulation_complete = Arc::new(AtomicBool::new(false));
     let simulation_complete_inner = simulation_complete.clone();
-    let (tx, rx) = crossbeam_channel::unbounded::<()>();
-    commands.insert_resource(AsyncSimulationSynchronization {
-        sender: tx,
-        complete: simulation_complete,
-    });
-
-    let future = async move {
-        loop {
+    let duration_nanos = Arc::new(AtomicU64::new(0));
+    let duration_nanos_inner = duration_nanos.clone();
+    let run: Arc<dyn Fn(audionimbus::SimulationFlags) + Send + Sync> =
+        Arc::new(move |flags: audionimbus::SimulationFlags| {
             {
                 // Block thread until simulator is ready
                 let simulator = simulator.read().unwrap();
-                simulator.run_reflections();
-                simulator.run_pathing();
+                let start = Instant::now();
+                if flags.contains(audionimbus::SimulationFlags::REFLECTIONS) {
+                    simulator.run_reflections();
+                }
+                if flags.contains(audionimbus::SimulationFlags::PATHING) {
+                    simulator.run_pathing();
+                }
+                let nanos = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+                duration_nanos_inner.store(nanos.max(1), Ordering::Relaxed);
             }
-
             simulation_complete_inner.store(true, Ordering::Relaxed);
-            if rx.recv().is_err() {
-                // tx dropped because we created a new simulation
-                break;
+        });
+    let (tx, rx) = crossbeam_channel::unbounded::<audionimbus::SimulationFlags>();
+    let run_inner = run.clone();
+    let simulate = move || {
+        run_inner(
+            audionimbus::SimulationFlags::REFLECTIONS | audionimbus::SimulationFlags::PATHING,
+        );
+        // Stops once tx is dropped because we created a new simulation or the app is exiting
+        while let Ok(flags) = rx.recv() {
+            run_inner(flags);
+        }
+    };
+    let sender = match *simulation_thread {
+        SteamAudioSimulationThread::TaskPool => {
+            AsyncComputeTaskPool::get()
+                .spawn(async move { simulate() })
+                .detach();
+            Some(tx)
+        }
+        SteamAudioSimulationThread::Dedicated { priority } => {
+            // The handle is dropped right away, detaching the thread
+            let spawned = std::thread::Builder::new()
+                .name("Steam Audio simulation".to_string())
+                .spawn(move || {
+                    priority.apply_to_current_thread();
+                    simulate();
+                });
+            match spawned {
+                Ok(_) => Some(tx),
+                Err(error) => {
+                    commands.trigger(SteamAudioError::SimulationThread { error });
+                    simulation_complete.store(true, Ordering::Relaxed);
+                    None
+                }
             }
         }
     };
-    AsyncComputeTaskPool::get().spawn(future).detach();
+    commands.insert_resource(AsyncSimulationSynchronization {
+        sender,
+        run,
+        complete: simulation_complete,
+        duration_nanos,
+    });
 
     commands.trigger(SteamAudioReady);
-    Ok(())
 }
 
+const DEFAULT_DISTANCE_ATTENUATION: SteamAudioDistanceAttenuation =
+    SteamAudioDistanceAttenuation::Default;
+
 /// Inspired by the Unity Steam Audio plugin.
 fn update_simulation(
     mut simulator: ResMut<AudionimbusSimulator>,
@@ -243,23 +681,42 @@ fn update_simulation(
     listener: Single<&GlobalTransform, With<SteamAudioListener>>,
     mut listener_source: ResMut<ListenerSource>,
     synchro: ResMut<AsyncSimulationSynchronization>,
-    mut root: ResMut<SteamAudioRootScene>,
-    mut nodes: Query<(&mut AudionimbusSource, &GlobalTransform, &SampleEffects)>,
+    (mut root, mut source_pool, mut retired_models): (
+        ResMut<SteamAudioRootScene>,
+        ResMut<SteamAudioSourcePool>,
+        ResMut<RetiredSourceModels>,
+    ),
+    mut nodes: Query<
+        (
+            Entity,
+            &mut AudionimbusSource,
+            &mut SourceAttenuationModel,
+            &mut SourceAirAbsorptionModel,
+            &GlobalTransform,
+            &SampleEffects,
+            Option<&SteamAudioDistanceAttenuation>,
+            Option<&SteamAudioAirAbsorption>,
+            Option<&SteamAudioSource>,
+        ),
+        Without<InactiveSource>,
+    >,
     mut steam_audio_nodes: Query<&mut SteamAudioNode>,
     mut reverb_node: Single<&mut SteamAudioReverbNode, Without<EffectOf>>,
 
     pathing_settings: Res<SteamAudioPathBakingSettings>,
     probes: Option<Res<SteamAudioProbeBatch>>,
-    time: Res<Time>,
-    mut errors: Local<Vec<String>>,
+    air_absorption: Res<SteamAudioAirAbsorption>,
+    // Grouped to stay within the system parameter limit
+    (budget, mut stats): (
+        Res<SteamAudioSimulationBudget>,
+        ResMut<SteamAudioSimulationStats>,
+    ),
+    (time, real_time): (Res<Time>, Res<Time<Real>>),
+    mut commands: Commands,
 ) -> Result {
-    if !enabled.enabled {
-        return Ok(());
-    }
-    errors.clear();
     let listener_transform = listener.compute_transform();
     let listener_orientation = listener_transform.into();
-    let shared_inputs = quality.to_audionimbus_simulation_shared_inputs(listener_orientation);
+    let mut shared_inputs = quality.to_audionimbus_simulation_shared_inputs(listener_orientation);
 
     if synchro.complete.load(Ordering::SeqCst) {
         root.commit();
@@ -269,14 +726,14 @@ fn update_simulation(
             .try_write()
             .map_err(|e| format!("Failed to commit simulator even though it should be idle: {e}"))?
             .commit();
+        source_pool.committed();
+        retired_models.clear();
     }
 
     let listener_inputs = audionimbus::SimulationInputs {
         source: listener_orientation.into(),
         direct_simulation: None,
-        reflections_simulation: Some(audionimbus::ReflectionsSimulationParameters::Convolution {
-            baked_data_identifier: None,
-        }),
+        reflections_simulation: Some(quality.reflections.kind.simulation_parameters()),
         pathing_simulation: probes.as_ref().map(|probes| {
             audionimbus::PathingSimulationParameters {
                 pathing_probes: probes,
@@ -291,66 +748,115 @@ fn update_simulation(
         }),
     };
     // TODO: make this configurable
-    let source_inputs = |orientation: AudionimbusCoordinateSystem| audionimbus::SimulationInputs {
-        source: orientation.into(),
-        direct_simulation: Some(audionimbus::DirectSimulationParameters {
-            distance_attenuation: Some(audionimbus::DistanceAttenuationModel::Default),
-            air_absorption: Some(audionimbus::AirAbsorptionModel::Default),
-            directivity: Some(audionimbus::Directivity::WeightedDipole {
-                // TODO: make sure this is synchronized with the encoder. Right now they both happen to hardcode the same values.
-                weight: 0.0,
-                power: 0.0,
-            }),
-            occlusion: Some(audionimbus::Occlusion {
-                transmission: Some(audionimbus::TransmissionParameters {
-                    num_transmission_rays: 16,
+    let source_inputs =
+        |orientation: AudionimbusCoordinateSystem,
+         (attenuation_model, attenuation_dirty): (&SourceAttenuationModel, bool),
+         (air_absorption_model, air_absorption_dirty): (&SourceAirAbsorptionModel, bool)| {
+            audionimbus::SimulationInputs {
+                source: orientation.into(),
+                direct_simulation: Some(audionimbus::DirectSimulationParameters {
+                    distance_attenuation: Some(attenuation_model.to_audionimbus(attenuation_dirty)),
+                    air_absorption: Some(air_absorption_model.to_audionimbus(air_absorption_dirty)),
+                    directivity: Some(audionimbus::Directivity::WeightedDipole {
+                        // TODO: make sure this is synchronized with the encoder. Right now they both happen to hardcode the same values.
+                        weight: 0.0,
+                        power: 0.0,
+                    }),
+                    occlusion: Some(audionimbus::Occlusion {
+                        transmission: Some(audionimbus::TransmissionParameters {
+                            num_transmission_rays: 16,
+                        }),
+                        algorithm: audionimbus::OcclusionAlgorithm::Volumetric {
+                            radius: 0.3,
+                            num_occlusion_samples: quality.direct.max_num_occlusion_samples,
+                        },
+                    }),
+                }),
+                reflections_simulation: Some(quality.reflections.kind.simulation_parameters()),
+                pathing_simulation: probes.as_ref().map(|probes| {
+                    audionimbus::PathingSimulationParameters {
+                        pathing_probes: probes,
+                        visibility_radius: pathing_settings.visibility_radius,
+                        visibility_threshold: pathing_settings.visibility_threshold,
+                        visibility_range: pathing_settings.visibility_range,
+                        pathing_order: quality.order,
+                        enable_validation: true,
+                        find_alternate_paths: true,
+                        deviation: audionimbus::DeviationModel::Default,
+                    }
                 }),
-                algorithm: audionimbus::OcclusionAlgorithm::Volumetric {
-                    radius: 0.3,
-                    num_occlusion_samples: quality.direct.max_num_occlusion_samples,
-                },
-            }),
-        }),
-        reflections_simulation: Some(audionimbus::ReflectionsSimulationParameters::Convolution {
-            baked_data_identifier: None,
-        }),
-        pathing_simulation: probes.as_ref().map(|probes| {
-            audionimbus::PathingSimulationParameters {
-                pathing_probes: probes,
-                visibility_radius: pathing_settings.visibility_radius,
-                visibility_threshold: pathing_settings.visibility_threshold,
-                visibility_range: pathing_settings.visibility_range,
-                pathing_order: quality.order,
-                enable_validation: true,
-                find_alternate_paths: true,
-                deviation: audionimbus::DeviationModel::Default,
             }
-        }),
-    };
+        };
 
     // set inputs
-    for (mut source, transform, effects) in nodes.iter_mut() {
-        let transform = transform.compute_transform();
+    for (
+        entity,
+        mut source,
+        mut attenuation_model,
+        mut air_absorption_model,
+        transform,
+        effects,
+        distance_attenuation,
+        source_air_absorption,
+        settings,
+    ) in nodes.iter_mut()
+    {
+        let mut transform = transform.compute_transform();
+        if settings.is_some_and(|settings| settings.head_locked) {
+            transform = listener_transform * transform;
+        }
         let orientation = transform.into();
+        let distance_attenuation = distance_attenuation.unwrap_or(&DEFAULT_DISTANCE_ATTENUATION);
+        let source_air_absorption = source_air_absorption.unwrap_or(&air_absorption);
+
+        let attenuation_dirty = *attenuation_model.0 != *distance_attenuation;
+        if attenuation_dirty {
+            let previous = std::mem::replace(
+                &mut *attenuation_model,
+                SourceAttenuationModel::new(distance_attenuation.clone()),
+            );
+            retired_models.attenuation.push(previous);
+        }
+        let air_absorption_dirty = *air_absorption_model.0 != *source_air_absorption;
+        if air_absorption_dirty {
+            let previous = std::mem::replace(
+                &mut *air_absorption_model,
+                SourceAirAbsorptionModel::new(source_air_absorption.clone()),
+            );
+            retired_models.air_absorption.push(previous);
+        }
 
         source.set_inputs(
             audionimbus::SimulationFlags::DIRECT,
-            source_inputs(orientation),
+            source_inputs(
+                orientation,
+                (&attenuation_model, attenuation_dirty),
+                (&air_absorption_model, air_absorption_dirty),
+            ),
         );
 
         let mut node = match steam_audio_nodes.get_effect_mut(effects) {
             Ok(node) => node,
             Err(err) => {
-                errors.push(format!("Failed to get Steam Audio node from source: {err}"));
+                commands.trigger(SteamAudioError::SourceNode { entity, error: err });
                 continue;
             }
         };
         node.source_position = orientation;
         node.listener_position = listener_orientation;
+        if node.distance_attenuation != *distance_attenuation {
+            node.distance_attenuation = distance_attenuation.clone();
+        }
+        if node.air_absorption != *source_air_absorption {
+            node.air_absorption = source_air_absorption.clone();
+        }
     }
 
     listener_source.set_inputs(audionimbus::SimulationFlags::DIRECT, listener_inputs);
     reverb_node.listener_position = listener_orientation;
+    if reverb_node.enabled != enabled.reverb {
+        reverb_node.enabled = enabled.reverb;
+    }
 
     let simulator = simulator
         .get()
@@ -359,34 +865,53 @@ fn update_simulation(
 
     simulator.set_shared_inputs(audionimbus::SimulationFlags::DIRECT, &shared_inputs);
 
-    simulator.run_direct();
+    if enabled.direct {
+        simulator.run_direct();
+    }
 
-    let Some(timer) = enabled.reflection_and_pathing_simulation_timer.as_mut() else {
-        // User doesn't want any reflection or pathing simulation
-        if errors.is_empty() {
-            return Ok(());
-        }
-        return Err(errors.join("\n").into());
-    };
+    // The listener reverb is simulated as reflections of the listener source
+    let mut flags = audionimbus::SimulationFlags::empty();
+    if enabled.reflections || enabled.reverb {
+        flags |= audionimbus::SimulationFlags::REFLECTIONS;
+    }
+    if enabled.pathing {
+        flags |= audionimbus::SimulationFlags::PATHING;
+    }
+    if flags.is_empty() {
+        // Nothing left to simulate asynchronously
+        return Ok(());
+    }
+
+    let timer = &mut enabled.reflection_and_pathing_simulation_timer;
     timer.tick(time.delta());
     if !timer.is_finished() {
         // Not yet time to kick off expensive simulation
-        if errors.is_empty() {
-            return Ok(());
-        }
-        return Err(errors.join("\n").into());
+        return Ok(());
     }
     if !synchro.complete.load(Ordering::SeqCst) {
         // It's time, but the previous simulation is still running!
-        if errors.is_empty() {
-            return Ok(());
-        }
-        return Err(errors.join("\n").into());
+        return Ok(());
+    }
+
+    let duration_nanos = synchro.duration_nanos.swap(0, Ordering::Relaxed);
+    if duration_nanos != 0 {
+        stats.record(
+            Duration::from_nanos(duration_nanos),
+            &budget,
+            timer.duration(),
+        );
+    }
+    let now = real_time.elapsed();
+    if now.saturating_sub(stats.last_start) < stats.interval {
+        // Starting now would exceed the CPU budget
+        return Ok(());
     }
+    stats.last_start = now;
 
     // The previous simulation is complete, so we can start the next one
 
     // set new inputs
+    stats.scale(&mut shared_inputs);
     simulator.set_shared_inputs(
         audionimbus::SimulationFlags::REFLECTIONS | audionimbus::SimulationFlags::PATHING,
         &shared_inputs,
@@ -397,18 +922,37 @@ fn update_simulation(
         listener_inputs,
     );
 
-    for (mut source, transform, effects) in nodes.iter_mut() {
-        let transform = transform.compute_transform();
+    for (
+        entity,
+        mut source,
+        attenuation_model,
+        air_absorption_model,
+        transform,
+        effects,
+        _,
+        _,
+        settings,
+    ) in nodes.iter_mut()
+    {
+        let mut transform = transform.compute_transform();
+        if settings.is_some_and(|settings| settings.head_locked) {
+            transform = listener_transform * transform;
+        }
         let orientation = transform.into();
 
+        // The models were already updated for the direct simulation
         source.set_inputs(
             audionimbus::SimulationFlags::REFLECTIONS | audionimbus::SimulationFlags::PATHING,
-            source_inputs(orientation),
+            source_inputs(
+                orientation,
+                (&attenuation_model, false),
+                (&air_absorption_model, false),
+            ),
         );
         let mut node = match steam_audio_nodes.get_effect_mut(effects) {
             Ok(node) => node,
             Err(err) => {
-                errors.push(format!("Failed to get Steam Audio node from source: {err}"));
+                commands.trigger(SteamAudioError::SourceNode { entity, error: err });
                 continue;
             }
         };
@@ -417,11 +961,89 @@ fn update_simulation(
 
     synchro.complete.store(false, Ordering::SeqCst);
     timer.reset();
-    synchro.sender.send(())?;
+    match &synchro.sender {
+        Some(sender) => sender.send(flags)?,
+        None => {
+            // The simulation takes the lock itself
+            drop(simulator);
+            (synchro.run)(flags);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_scales_down_when_over_and_recovers_when_under() {
+        let budget = SteamAudioSimulationBudget {
+            cpu_time_per_second: Some(Duration::from_millis(500)),
+            max_interval: Duration::from_millis(400),
+            min_quality_scale: 0.25,
+        };
+        let min_interval = Duration::from_millis(100);
+        let mut stats = SteamAudioSimulationStats::default();
+
+        // 250 ms per simulation needs 500 ms between them to stay within 500 ms per second
+        stats.record(Duration::from_millis(250), &budget, min_interval);
+        assert_eq!(stats.interval, Duration::from_millis(500));
+        assert_eq!(stats.quality_scale, 0.8);
+        for _ in 0..20 {
+            stats.record(Duration::from_millis(250), &budget, min_interval);
+        }
+        assert_eq!(stats.quality_scale, budget.min_quality_scale);
+
+        // Cheap simulations run at the rate of the timer and recover the full quality
+        for _ in 0..50 {
+            stats.record(Duration::from_millis(10), &budget, min_interval);
+        }
+        assert_eq!(stats.interval, min_interval);
+        assert_eq!(stats.quality_scale, 1.0);
+    }
+
+    #[test]
+    fn budget_is_ignored_without_cpu_time() {
+        let budget = SteamAudioSimulationBudget {
+            cpu_time_per_second: None,
+            ..default()
+        };
+        let min_interval = Duration::from_millis(100);
+        let mut stats = SteamAudioSimulationStats {
+            quality_scale: 0.5,
+            ..default()
+        };
+        stats.record(Duration::from_secs(1), &budget, min_interval);
+        assert_eq!(stats.interval, min_interval);
+        assert_eq!(stats.quality_scale, 1.0);
+    }
 
-    if errors.is_empty() {
-        Ok(())
-    } else {
-        Err(errors.join("\n").into())
+    #[test]
+    fn scales_rays_and_bounces() {
+        let quality = SteamAudioQuality::default();
+        let mut inputs =
+            quality.to_audionimbus_simulation_shared_inputs(AudionimbusCoordinateSystem::default());
+        let stats = SteamAudioSimulationStats {
+            quality_scale: 0.5,
+            ..default()
+        };
+        stats.scale(&mut inputs);
+        assert_eq!(
+            inputs.num_rays,
+            (quality.reflections.num_rays as f32 * 0.5) as u32
+        );
+        assert_eq!(
+            inputs.num_bounces,
+            (quality.num_bounces as f32 * 0.5).round() as u32
+        );
+
+        let stats = SteamAudioSimulationStats {
+            quality_scale: 0.0,
+            ..default()
+        };
+        stats.scale(&mut inputs);
+        assert_eq!(inputs.num_rays, 1);
+        assert_eq!(inputs.num_bounces, 1);
     }
 }
@@ -1,37 +1,153 @@
 use bevy_ecs::entity_disabling::Disabled;
 use bevy_seedling::{
     node::follower::FollowerOf,
-    prelude::{AudioEvents, EffectOf, EffectsQuery, SampleEffects},
+    pool::Sampler,
+    prelude::{AudioEvents, EffectOf, EffectsQuery, PlaybackSettings, SampleEffects},
+};
+use firewheel::{
+    diff::{Diff, EventQueue as _, Patch, RealtimeClone},
+    event::NodeEventType,
 };
-use firewheel::{diff::EventQueue as _, event::NodeEventType};
 
-use crate::{prelude::*, simulation::AudionimbusSimulator};
+use crate::{
+    models::{SourceAirAbsorptionModel, SourceAttenuationModel},
+    prelude::*,
+    settings::SteamAudioEnabled,
+    simulation::{AudionimbusSimulator, SteamAudioSourcePool},
+};
 
 pub(super) fn plugin(app: &mut App) {
+    let schedule = SteamAudioSchedule::from_app(app);
     app.init_resource::<ToSetup>()
         .init_resource::<SourcesToRemove>();
     app.add_observer(remove_steam_audio_source)
         .add_observer(queue_audionimbus_source_init)
         .add_observer(send_source_to_processor);
     app.add_systems(
-        PostUpdate,
+        schedule,
         (
             send_source_to_reverb_processor,
+            forward_source_settings,
             drain_to_remove,
             init_audionimbus_sources.run_if(resource_exists::<AudionimbusSimulator>),
+            sync_source_activity.run_if(resource_exists::<AudionimbusSimulator>),
         )
             .chain()
             .in_set(SteamAudioSystems::UpdateSources),
     );
 }
 
+/// Per-source mix settings for a [`SamplePlayer`](bevy_seedling::prelude::SamplePlayer) played through a Steam Audio pool.
+///
+/// These are forwarded to the [`SteamAudioNode`] of the sample player every frame.
+/// The gains and the spatial blend are plain `f32`s, so they can be animated with Bevy's animation curves,
+/// e.g. via `animated_field!(SteamAudioSource::direct_gain)`.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component, Default, Debug, Clone, PartialEq)]
+pub struct SteamAudioSource {
+    /// The volume of the direct path.
+    pub direct_gain: f32,
+    /// The volume of the reflections.
+    pub reflection_gain: f32,
+    /// The volume of the sound propagated along paths through the probe batch.
+    pub pathing_gain: f32,
+    /// Blends between unspatialized (0.0) and fully spatialized (1.0) rendering of the direct path.
+    /// Lowering this as the listener gets close lets e.g. diegetic music fade into plain stereo.
+    pub spatial_blend: f32,
+    /// How the HRTF is sampled for directions that were not measured.
+    pub hrtf_interpolation: SteamAudioHrtfInterpolation,
+    /// If `true`, the [`GlobalTransform`] of the sample player is interpreted relative to the [`SteamAudioListener`](crate::SteamAudioListener),
+    /// so the source moves and turns together with the listener's head.
+    pub head_locked: bool,
+}
+
+/// How the HRTF is sampled when rendering a direction that is not contained in the measured HRTF data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect, Diff, Patch, RealtimeClone)]
+#[reflect(Default, Debug, Clone, PartialEq, Hash)]
+pub enum SteamAudioHrtfInterpolation {
+    /// Selects the closest measured direction.
+    /// This is cheap, so use it for less important sources.
+    Nearest,
+    /// Interpolates between the closest measured directions.
+    /// This is considerably more expensive, but most noticeable for wide-band noise-like sounds, such as fire or radio static.
+    #[default]
+    Bilinear,
+}
+
+impl From<SteamAudioHrtfInterpolation> for audionimbus::HrtfInterpolation {
+    fn from(interpolation: SteamAudioHrtfInterpolation) -> Self {
+        match interpolation {
+            SteamAudioHrtfInterpolation::Nearest => audionimbus::HrtfInterpolation::Nearest,
+            SteamAudioHrtfInterpolation::Bilinear => audionimbus::HrtfInterpolation::Bilinear,
+        }
+    }
+}
+
+impl Default for SteamAudioSource {
+    fn default() -> Self {
+        Self {
+            direct_gain: 1.0,
+            reflection_gain: 1.0,
+            pathing_gain: 1.0,
+            spatial_blend: 1.0,
+            hrtf_interpolation: SteamAudioHrtfInterpolation::default(),
+            head_locked: false,
+        }
+    }
+}
+
+fn forward_source_settings(
+    sources: Query<(Option<&SteamAudioSource>, &SampleEffects), With<AudionimbusSource>>,
+    mut nodes: Query<&mut SteamAudioNode>,
+    enabled: Res<SteamAudioEnabled>,
+) {
+    // Disabled paths are faded out by the node's gain ramps
+    let toggle = |enabled: bool| if enabled { 1.0 } else { 0.0 };
+    for (source, effects) in &sources {
+        let Ok(mut node) = nodes.get_effect_mut(effects) else {
+            continue;
+        };
+        // Sources without settings still need to be forwarded the defaults,
+        // as the node might have been used by a different sample player before.
+        let source = source.copied().unwrap_or_default();
+        let direct_gain = source.direct_gain * toggle(enabled.direct);
+        if node.direct_gain != direct_gain {
+            node.direct_gain = direct_gain;
+        }
+        let reflection_gain = source.reflection_gain * toggle(enabled.reflections);
+        if node.reflection_gain != reflection_gain {
+            node.reflection_gain = reflection_gain;
+        }
+        let pathing_gain = source.pathing_gain * toggle(enabled.pathing);
+        if node.pathing_gain != pathing_gain {
+            node.pathing_gain = pathing_gain;
+        }
+        if node.spatial_blend != source.spatial_blend {
+            node.spatial_blend = source.spatial_blend;
+        }
+        if node.hrtf_interpolation != source.hrtf_interpolation {
+            node.hrtf_interpolation = source.hrtf_interpolation;
+        }
+    }
+}
+
 #[derive(Resource, Deref, DerefMut)]
 pub struct ListenerSource(pub(crate) audionimbus::Source);
 
 #[derive(Component, Deref, DerefMut)]
-#[require(Transform, GlobalTransform)]
+#[require(
+    Transform,
+    GlobalTransform,
+    SourceAttenuationModel,
+    SourceAirAbsorptionModel
+)]
 pub struct AudionimbusSource(pub(crate) audionimbus::Source);
 
+/// Marks an [`AudionimbusSource`] whose sample is not playing.
+/// Its source is removed from the simulator so it doesn't take up simulation slots until playback resumes.
+#[derive(Component, Debug)]
+pub(crate) struct InactiveSource;
+
 fn send_source_to_processor(
     add: On<Add, AudionimbusSource>,
     effects: Query<(&AudionimbusSource, &SampleEffects), Allow<Disabled>>,
@@ -74,32 +190,23 @@ fn init_audionimbus_sources(
     mut commands: Commands,
     mut to_setup: ResMut<ToSetup>,
     mut simulator: ResMut<AudionimbusSimulator>,
-    mut errors: Local<Vec<String>>,
-    names: Query<NameOrEntity>,
+    mut source_pool: ResMut<SteamAudioSourcePool>,
     mut to_retry: Local<Vec<Entity>>,
-) -> Result {
-    errors.clear();
+) {
     if to_setup.is_empty() {
-        return Ok(());
+        return;
     }
     let Ok(simulator) = simulator.get().try_read() else {
-        return Ok(());
+        return;
     };
     for entity in to_setup.drain(..) {
         if commands.get_entity(entity).is_err() {
             continue;
         }
-        let name = names.get(entity).unwrap();
-
-        let source = match audionimbus::Source::try_new(
-            &simulator,
-            &audionimbus::SourceSettings {
-                flags: audionimbus::SimulationFlags::all(),
-            },
-        ) {
+        let source = match source_pool.take(&simulator) {
             Ok(source) => source,
             Err(err) => {
-                errors.push(format!("{name} Failed to create AudionimbusSource: {err}"));
+                commands.trigger(SteamAudioError::SourceCreation { entity, error: err });
                 continue;
             }
         };
@@ -111,24 +218,56 @@ fn init_audionimbus_sources(
     for entity in to_retry.drain(..) {
         to_setup.push(entity);
     }
-
-    if errors.is_empty() {
-        Ok(())
-    } else {
-        Err(errors.join("\n").into())
-    }
 }
 
+// `Replace` also covers a sample player being assigned a new source.
 fn remove_steam_audio_source(
-    remove: On<Remove, AudionimbusSource>,
-    source: Query<&AudionimbusSource, Allow<Disabled>>,
+    replace: On<Replace, AudionimbusSource>,
+    source: Query<(&AudionimbusSource, Has<InactiveSource>), Allow<Disabled>>,
     mut to_remove: ResMut<SourcesToRemove>,
+    mut source_pool: ResMut<SteamAudioSourcePool>,
 ) -> Result {
-    let source = source.get(remove.entity)?;
-    to_remove.0.push(source.0.clone());
+    let (source, inactive) = source.get(replace.entity)?;
+    if !inactive {
+        to_remove.0.push(source.0.clone());
+    }
+    source_pool.release(source.0.clone());
     Ok(())
 }
 
+fn sync_source_activity(
+    mut simulator: ResMut<AudionimbusSimulator>,
+    sources: Query<(
+        Entity,
+        &AudionimbusSource,
+        &SampleEffects,
+        Option<&Sampler>,
+        Option<&PlaybackSettings>,
+        Has<InactiveSource>,
+    )>,
+    mut nodes: Query<&mut SteamAudioNode>,
+    mut commands: Commands,
+) {
+    let Ok(simulator) = simulator.get().try_read() else {
+        return;
+    };
+    for (entity, source, effects, sampler, settings, inactive) in &sources {
+        let playing = sampler.is_some_and(Sampler::is_playing)
+            && settings.is_none_or(|settings| *settings.play);
+        if playing && inactive {
+            simulator.add_source(source);
+            commands.entity(entity).try_remove::<InactiveSource>();
+            // The effects still hold the state from before the source became inactive
+            if let Ok(mut node) = nodes.get_effect_mut(effects) {
+                node.reset.notify();
+            }
+        } else if !playing && !inactive {
+            simulator.remove_source(source);
+            commands.entity(entity).try_insert(InactiveSource);
+        }
+    }
+}
+
 #[derive(Resource, Default, Deref, DerefMut)]
 pub(crate) struct SourcesToRemove(pub(crate) Vec<audionimbus::Source>);
 
@@ -9,8 +9,12 @@ pub(super) fn plugin(app: &mut App) {
 /// You can specify the acoustic material properties of each triangle, although typically many triangles will share a common material.
 ///
 /// The acoustic material properties are specified for three frequency bands with center frequencies of 400 Hz, 2.5 `KHz`, and 15 `KHz`.
+///
+/// Changing this component rebuilds the entity's Steam Audio mesh, e.g. to let more sound through an opening door.
+/// Rebuilding is not free, so avoid changing the materials of large meshes every frame.
 #[derive(Component, Copy, Clone, PartialEq, Debug, Reflect)]
 #[reflect(Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SteamAudioMaterial {
     /// Fraction of sound energy absorbed at low, middle, high frequencies.
     ///
@@ -28,6 +32,30 @@ pub struct SteamAudioMaterial {
     pub transmission: [f32; 3],
 }
 
+/// Per-triangle acoustic materials for a mesh, so that it becomes a single Steam Audio mesh with multiple materials.
+///
+/// Each triangle picks its material from [`ATTRIBUTE_STEAM_AUDIO_MATERIAL`](crate::wrapper::ATTRIBUTE_STEAM_AUDIO_MATERIAL) if the mesh has it,
+/// and from [`Self::index_ranges`] otherwise.
+/// Triangles without a valid material use the entity's [`SteamAudioMaterial`].
+#[derive(Component, Clone, PartialEq, Debug, Default, Reflect)]
+#[reflect(Component, Default)]
+#[require(SteamAudioMaterial)]
+pub struct SteamAudioMaterials {
+    /// The materials triangles can refer to by their index in this list.
+    pub materials: Vec<SteamAudioMaterial>,
+    /// Assigns materials to ranges of the mesh's indices, like the submeshes of many model formats.
+    pub index_ranges: Vec<SteamAudioMaterialRange>,
+}
+
+/// Assigns a material of [`SteamAudioMaterials`] to a range of a mesh's indices.
+#[derive(Clone, PartialEq, Eq, Debug, Reflect)]
+pub struct SteamAudioMaterialRange {
+    /// The range of indices. A triangle belongs to the range that contains its first index.
+    pub indices: core::ops::Range<u32>,
+    /// The index into [`SteamAudioMaterials::materials`].
+    pub material: u32,
+}
+
 impl From<audionimbus::Material> for SteamAudioMaterial {
     fn from(material: audionimbus::Material) -> Self {
         Self {
@@ -55,6 +83,32 @@ impl Default for SteamAudioMaterial {
 }
 
 impl SteamAudioMaterial {
+    /// Returns the constant with the given name, ignoring case, e.g. `"brick"` for [`Self::BRICK`].
+    pub fn from_preset_name(name: &str) -> Option<Self> {
+        let preset = match name.to_ascii_lowercase().as_str() {
+            "generic" => Self::GENERIC,
+            "brick" => Self::BRICK,
+            "concrete" => Self::CONCRETE,
+            "ceramic" => Self::CERAMIC,
+            "gravel" => Self::GRAVEL,
+            "carpet" => Self::CARPET,
+            "glass" => Self::GLASS,
+            "plaster" => Self::PLASTER,
+            "wood" => Self::WOOD,
+            "metal" => Self::METAL,
+            "rock" => Self::ROCK,
+            _ => return None,
+        };
+        Some(preset)
+    }
+
+    /// The bit patterns of all fields, e.g. to use the material as part of a hash key.
+    pub fn to_bits(&self) -> [u32; 7] {
+        let [a0, a1, a2] = self.absorption.map(f32::to_bits);
+        let [t0, t1, t2] = self.transmission.map(f32::to_bits);
+        [a0, a1, a2, self.scattering.to_bits(), t0, t1, t2]
+    }
+
     pub const GENERIC: Self = Self {
         absorption: [0.10, 0.20, 0.30],
         scattering: 0.05,
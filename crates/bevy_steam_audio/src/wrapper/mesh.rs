@@ -1,18 +1,41 @@
-use bevy_mesh::PrimitiveTopology;
-use itertools::Itertools as _;
+use bevy_mesh::{
+    Indices, MeshVertexAttribute, PrimitiveTopology, VertexAttributeValues, VertexFormat,
+};
+use bevy_platform::collections::HashMap;
 
-use crate::prelude::*;
+use crate::{
+    error::MeshConversionError,
+    prelude::*,
+    wrapper::{SteamAudioMaterial, SteamAudioMaterials},
+};
 
 pub(super) fn plugin(app: &mut App) {
     let _ = app;
 }
 
+/// A custom vertex attribute holding an index into [`SteamAudioMaterials::materials`].
+/// Each triangle uses the material of its first vertex.
+pub const ATTRIBUTE_STEAM_AUDIO_MATERIAL: MeshVertexAttribute = MeshVertexAttribute::new(
+    "Vertex_SteamAudioMaterial",
+    0x5a7e_a0d1_0000_0001,
+    VertexFormat::Uint32,
+);
+
 pub trait ToSteamAudioMesh {
     fn to_steam_audio_mesh(
         &self,
         scene: &audionimbus::Scene,
         material: audionimbus::Material,
-    ) -> Result<audionimbus::StaticMesh>;
+    ) -> Result<audionimbus::StaticMesh, MeshConversionError>;
+
+    /// Like [`Self::to_steam_audio_mesh`], but with per-triangle materials.
+    /// `material` is used for triangles without a valid material.
+    fn to_steam_audio_mesh_with_materials(
+        &self,
+        scene: &audionimbus::Scene,
+        material: audionimbus::Material,
+        materials: &SteamAudioMaterials,
+    ) -> Result<audionimbus::StaticMesh, MeshConversionError>;
 }
 
 impl ToSteamAudioMesh for Mesh {
@@ -20,37 +43,404 @@ impl ToSteamAudioMesh for Mesh {
         &self,
         scene: &audionimbus::Scene,
         material: audionimbus::Material,
-    ) -> Result<audionimbus::StaticMesh> {
-        if self.primitive_topology() != PrimitiveTopology::TriangleList {
-            return Err("Mesh is not a triangle list".into());
-        }
-        let vertices = self
+    ) -> Result<audionimbus::StaticMesh, MeshConversionError> {
+        self.to_steam_audio_mesh_with_materials(scene, material, &SteamAudioMaterials::default())
+    }
+
+    fn to_steam_audio_mesh_with_materials(
+        &self,
+        scene: &audionimbus::Scene,
+        material: audionimbus::Material,
+        materials: &SteamAudioMaterials,
+    ) -> Result<audionimbus::StaticMesh, MeshConversionError> {
+        let positions = self
             .attribute(Mesh::ATTRIBUTE_POSITION)
-            .ok_or("Mesh has no position attribute")?
+            .ok_or(MeshConversionError::NoPositions)?
             .as_float3()
-            .ok_or("Mesh position attribute is not a float3")?
-            .iter()
-            .map(|v| audionimbus::Vector3::from(*v))
+            .ok_or(MeshConversionError::PositionsNotFloat3)?;
+        let mesh_triangles = MeshTriangles::try_from(self)?;
+        // Materials refer to the original vertices and indices, so they are looked up before welding.
+        let material_indices = material_indices(self, &mesh_triangles, materials);
+        let MeshTriangles { mut triangles, .. } = mesh_triangles;
+        let vertices = weld_vertices(positions, &mut triangles)
+            .into_iter()
+            .map(audionimbus::Vector3::from)
             .collect::<Vec<_>>();
-        let triangles = self
-            .indices()
-            .ok_or("Mesh has no indices")?
-            .iter()
-            .chunks(3)
+        let (triangles, material_indices): (Vec<_>, Vec<_>) = triangles
             .into_iter()
-            .map(|mut chunk| {
-                let v0 = chunk.next().unwrap();
-                let v1 = chunk.next().unwrap();
-                let v2 = chunk.next().unwrap();
-                audionimbus::Triangle::new(v0 as i32, v1 as i32, v2 as i32)
+            .zip(material_indices)
+            .filter(|(triangle, _)| !is_degenerate(triangle))
+            .map(|([v0, v1, v2], material)| {
+                (
+                    audionimbus::Triangle::new(v0 as i32, v1 as i32, v2 as i32),
+                    material,
+                )
             })
-            .collect::<Vec<_>>();
+            .unzip();
+        // Index 0 is the fallback, so the per-triangle materials are shifted by one.
+        let materials = core::iter::once(material)
+            .chain(
+                materials
+                    .materials
+                    .iter()
+                    .copied()
+                    .map(SteamAudioMaterial::into),
+            )
+            .collect::<Vec<audionimbus::Material>>();
         let settings = audionimbus::StaticMeshSettings {
             vertices: &vertices,
             triangles: &triangles,
-            material_indices: &vec![0; triangles.len()],
-            materials: &[material],
+            material_indices: &material_indices,
+            materials: &materials,
         };
         audionimbus::StaticMesh::try_new(scene, &settings).map_err(Into::into)
     }
 }
+
+/// The triangles of a mesh, independent of its topology and whether it is indexed.
+pub(crate) struct MeshTriangles {
+    /// Indices into the mesh's vertices.
+    pub(crate) triangles: Vec<[u32; 3]>,
+    /// The position of each triangle's first index in the mesh's indices.
+    pub(crate) first_indices: Vec<u32>,
+}
+
+impl TryFrom<&Mesh> for MeshTriangles {
+    type Error = MeshConversionError;
+
+    fn try_from(mesh: &Mesh) -> Result<Self, Self::Error> {
+        // Non-indexed meshes implicitly use every vertex in order
+        let (indices, restart) = match mesh.indices() {
+            Some(Indices::U16(indices)) => (
+                indices.iter().copied().map(u32::from).collect(),
+                Some(u32::from(u16::MAX)),
+            ),
+            Some(Indices::U32(indices)) => (indices.clone(), Some(u32::MAX)),
+            None => ((0..mesh.count_vertices() as u32).collect::<Vec<_>>(), None),
+        };
+
+        let mut mesh_triangles = Self {
+            triangles: Vec::new(),
+            first_indices: Vec::new(),
+        };
+        match mesh.primitive_topology() {
+            PrimitiveTopology::TriangleList => {
+                for (i, chunk) in indices.chunks_exact(3).enumerate() {
+                    mesh_triangles.push([chunk[0], chunk[1], chunk[2]], i as u32 * 3);
+                }
+            }
+            PrimitiveTopology::TriangleStrip => {
+                // The maximum index value restarts the strip
+                let mut offset = 0;
+                for strip in indices.split(|&index| Some(index) == restart) {
+                    for (i, window) in strip.windows(3).enumerate() {
+                        // Every other triangle of a strip has its winding reversed
+                        let triangle = if i % 2 == 0 {
+                            [window[0], window[1], window[2]]
+                        } else {
+                            [window[1], window[0], window[2]]
+                        };
+                        mesh_triangles.push(triangle, (offset + i) as u32);
+                    }
+                    offset += strip.len() + 1;
+                }
+            }
+            _ => return Err(MeshConversionError::NotTriangles),
+        }
+
+        let vertex_count = mesh.count_vertices() as u32;
+        let (triangles, first_indices) = mesh_triangles
+            .triangles
+            .into_iter()
+            .zip(mesh_triangles.first_indices)
+            .filter(|(triangle, _)| triangle.iter().all(|&index| index < vertex_count))
+            .unzip();
+        Ok(Self {
+            triangles,
+            first_indices,
+        })
+    }
+}
+
+impl MeshTriangles {
+    fn push(&mut self, triangle: [u32; 3], first_index: u32) {
+        self.triangles.push(triangle);
+        self.first_indices.push(first_index);
+    }
+}
+
+/// Vertices closer than this distance in meters are welded together.
+pub(crate) const WELD_TOLERANCE: f32 = 1.0e-4;
+
+/// Merges vertices closer than [`WELD_TOLERANCE`] and remaps `triangles` to the merged vertices.
+pub(crate) fn weld_vertices(positions: &[[f32; 3]], triangles: &mut [[u32; 3]]) -> Vec<[f32; 3]> {
+    let mut welded = Vec::<[f32; 3]>::new();
+    let mut grid = HashMap::<[i64; 3], Vec<u32>>::default();
+    let remap = positions
+        .iter()
+        .map(|position| {
+            let [x, y, z] = position.map(|coordinate| (coordinate / WELD_TOLERANCE).floor() as i64);
+            // A vertex within the tolerance lies at most one cell away
+            let existing = (-1..=1)
+                .flat_map(|dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| [dx, dy, dz])))
+                .filter_map(|[dx, dy, dz]| grid.get(&[x + dx, y + dy, z + dz]))
+                .flatten()
+                .copied()
+                .find(|&index| {
+                    Vec3::from(welded[index as usize]).distance_squared(Vec3::from(*position))
+                        <= WELD_TOLERANCE * WELD_TOLERANCE
+                });
+            existing.unwrap_or_else(|| {
+                welded.push(*position);
+                let index = welded.len() as u32 - 1;
+                grid.entry([x, y, z]).or_default().push(index);
+                index
+            })
+        })
+        .collect::<Vec<_>>();
+    for index in triangles.iter_mut().flatten() {
+        *index = remap[*index as usize];
+    }
+    welded
+}
+
+/// Whether a triangle has collapsed into a line or point, e.g. after welding.
+pub(crate) fn is_degenerate([v0, v1, v2]: &[u32; 3]) -> bool {
+    v0 == v1 || v1 == v2 || v0 == v2
+}
+
+/// Returns the index into the Steam Audio materials for each triangle, where 0 is the fallback material.
+fn material_indices(
+    mesh: &Mesh,
+    mesh_triangles: &MeshTriangles,
+    materials: &SteamAudioMaterials,
+) -> Vec<i32> {
+    let triangles = &mesh_triangles.triangles;
+    let to_steam_audio_index = |material: Option<u32>| {
+        material
+            .filter(|&material| (material as usize) < materials.materials.len())
+            .map_or(0, |material| material as i32 + 1)
+    };
+    if materials.materials.is_empty() {
+        return vec![0; triangles.len()];
+    }
+    if let Some(VertexAttributeValues::Uint32(vertex_materials)) =
+        mesh.attribute(ATTRIBUTE_STEAM_AUDIO_MATERIAL)
+    {
+        return triangles
+            .iter()
+            .map(|[v0, ..]| to_steam_audio_index(vertex_materials.get(*v0 as usize).copied()))
+            .collect();
+    }
+    mesh_triangles
+        .first_indices
+        .iter()
+        .map(|first_index| {
+            let material = materials
+                .index_ranges
+                .iter()
+                .find(|range| range.indices.contains(first_index))
+                .map(|range| range.material);
+            to_steam_audio_index(material)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::RenderAssetUsages;
+
+    use super::*;
+    use crate::wrapper::SteamAudioMaterialRange;
+
+    fn mesh(topology: PrimitiveTopology, vertex_count: usize, indices: Option<Indices>) -> Mesh {
+        let positions = (0..vertex_count)
+            .map(|i| [i as f32, (i % 2) as f32, 0.0])
+            .collect::<Vec<_>>();
+        let mesh = Mesh::new(topology, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        match indices {
+            Some(indices) => mesh.with_inserted_indices(indices),
+            None => mesh,
+        }
+    }
+
+    #[test]
+    fn splits_triangle_list() {
+        let mesh = mesh(
+            PrimitiveTopology::TriangleList,
+            4,
+            Some(Indices::U32(vec![0, 1, 2, 2, 1, 3])),
+        );
+        let triangles = MeshTriangles::try_from(&mesh).unwrap();
+        assert_eq!(triangles.triangles, vec![[0, 1, 2], [2, 1, 3]]);
+        assert_eq!(triangles.first_indices, vec![0, 3]);
+    }
+
+    #[test]
+    fn alternates_strip_winding() {
+        let mesh = mesh(
+            PrimitiveTopology::TriangleStrip,
+            5,
+            Some(Indices::U16(vec![0, 1, 2, 3, 4])),
+        );
+        let triangles = MeshTriangles::try_from(&mesh).unwrap();
+        assert_eq!(triangles.triangles, vec![[0, 1, 2], [2, 1, 3], [2, 3, 4]]);
+        assert_eq!(triangles.first_indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn restarts_strips() {
+        let mesh = mesh(
+            PrimitiveTopology::TriangleStrip,
+            8,
+            Some(Indices::U32(vec![0, 1, 2, 3, u32::MAX, 4, 5, 6, 7])),
+        );
+        let triangles = MeshTriangles::try_from(&mesh).unwrap();
+        // The winding starts over with every strip
+        assert_eq!(
+            triangles.triangles,
+            vec![[0, 1, 2], [2, 1, 3], [4, 5, 6], [6, 5, 7]]
+        );
+        assert_eq!(triangles.first_indices, vec![0, 1, 5, 6]);
+
+        let mesh = mesh(
+            PrimitiveTopology::TriangleStrip,
+            6,
+            Some(Indices::U16(vec![0, 1, 2, u16::MAX, 3, 4, 5])),
+        );
+        let triangles = MeshTriangles::try_from(&mesh).unwrap();
+        assert_eq!(triangles.triangles, vec![[0, 1, 2], [3, 4, 5]]);
+        assert_eq!(triangles.first_indices, vec![0, 4]);
+    }
+
+    #[test]
+    fn uses_vertex_order_without_indices() {
+        let mesh = mesh(PrimitiveTopology::TriangleStrip, 4, None);
+        let triangles = MeshTriangles::try_from(&mesh).unwrap();
+        assert_eq!(triangles.triangles, vec![[0, 1, 2], [2, 1, 3]]);
+    }
+
+    #[test]
+    fn drops_out_of_range_triangles() {
+        let mesh = mesh(
+            PrimitiveTopology::TriangleList,
+            3,
+            Some(Indices::U32(vec![0, 1, 3, 0, 1, 2])),
+        );
+        let triangles = MeshTriangles::try_from(&mesh).unwrap();
+        assert_eq!(triangles.triangles, vec![[0, 1, 2]]);
+        assert_eq!(triangles.first_indices, vec![3]);
+    }
+
+    #[test]
+    fn rejects_non_triangles() {
+        let mesh = mesh(PrimitiveTopology::LineList, 4, None);
+        assert!(matches!(
+            MeshTriangles::try_from(&mesh),
+            Err(MeshConversionError::NotTriangles)
+        ));
+    }
+
+    #[test]
+    fn welds_vertices_within_tolerance() {
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [-0.0, 0.0, WELD_TOLERANCE * 0.5],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0 + WELD_TOLERANCE * 2.0, 0.0],
+        ];
+        let mut triangles = [[0, 1, 2], [3, 4, 5]];
+        let welded = weld_vertices(&positions, &mut triangles);
+        assert_eq!(
+            welded,
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, 1.0 + WELD_TOLERANCE * 2.0, 0.0],
+            ]
+        );
+        assert_eq!(triangles, [[0, 1, 2], [0, 1, 3]]);
+    }
+
+    #[test]
+    fn welds_across_grid_cells() {
+        // Both positions are within the tolerance but on either side of a cell boundary
+        let positions = [
+            [-WELD_TOLERANCE * 0.25, 0.0, 0.0],
+            [WELD_TOLERANCE * 0.25, 0.0, 0.0],
+        ];
+        let mut triangles = [[0, 1, 1]];
+        let welded = weld_vertices(&positions, &mut triangles);
+        assert_eq!(welded.len(), 1);
+        assert_eq!(triangles, [[0, 0, 0]]);
+    }
+
+    #[test]
+    fn drops_zero_area_triangles() {
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [1.0, 0.0, 0.0],
+        ];
+        let mut triangles = vec![[0, 1, 2], [0, 1, 3], [2, 2, 0]];
+        weld_vertices(&positions, &mut triangles);
+        triangles.retain(|triangle| !is_degenerate(triangle));
+        assert_eq!(triangles, vec![[0, 1, 2]]);
+    }
+
+    #[test]
+    fn reads_materials_from_vertex_attribute() {
+        let mesh = mesh(
+            PrimitiveTopology::TriangleList,
+            4,
+            Some(Indices::U32(vec![0, 1, 2, 3, 2, 1, 1, 2, 3])),
+        )
+        .with_inserted_attribute(ATTRIBUTE_STEAM_AUDIO_MATERIAL, vec![1_u32, 0, 0, 7]);
+        let materials = SteamAudioMaterials {
+            materials: vec![SteamAudioMaterial::BRICK, SteamAudioMaterial::WOOD],
+            index_ranges: Vec::new(),
+        };
+        let triangles = MeshTriangles::try_from(&mesh).unwrap();
+        // The first vertex decides, and invalid materials fall back to 0
+        assert_eq!(
+            material_indices(&mesh, &triangles, &materials),
+            vec![2, 0, 1]
+        );
+    }
+
+    #[test]
+    fn reads_materials_from_index_ranges() {
+        let mesh = mesh(
+            PrimitiveTopology::TriangleList,
+            4,
+            Some(Indices::U32(vec![0, 1, 2, 2, 1, 3, 1, 2, 3])),
+        );
+        let materials = SteamAudioMaterials {
+            materials: vec![SteamAudioMaterial::BRICK, SteamAudioMaterial::WOOD],
+            index_ranges: vec![
+                SteamAudioMaterialRange {
+                    indices: 0..3,
+                    material: 1,
+                },
+                SteamAudioMaterialRange {
+                    indices: 3..6,
+                    material: 0,
+                },
+            ],
+        };
+        let triangles = MeshTriangles::try_from(&mesh).unwrap();
+        assert_eq!(
+            material_indices(&mesh, &triangles, &materials),
+            vec![2, 1, 0]
+        );
+        assert_eq!(
+            material_indices(&mesh, &triangles, &SteamAudioMaterials::default()),
+            vec![0, 0, 0]
+        );
+    }
+}
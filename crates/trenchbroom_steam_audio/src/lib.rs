@@ -6,10 +6,11 @@ use bevy_ecs::{entity_disabling::Disabled, prelude::*};
 use bevy_mesh::prelude::*;
 use bevy_scene::prelude::*;
 use bevy_steam_audio::{
-    STEAM_AUDIO_CONTEXT, audionimbus,
+    STEAM_AUDIO_CONTEXT, SteamAudioSchedule, SteamAudioSystems, audionimbus,
+    error::{MeshConversionError, SteamAudioError},
     scene::{
-        InSteamAudioMeshSpawnQueue, Static, SteamAudioInstancedMesh, SteamAudioRootScene,
-        SteamAudioStaticMesh,
+        InSteamAudioMeshSpawnQueue, NotSteamAudioMesh, Static, SteamAudioInstancedMesh,
+        SteamAudioRootScene, SteamAudioStaticMesh, material_mapping::SteamAudioMaterialMapping,
     },
     wrapper::{SteamAudioMaterial, ToSteamAudioMesh as _, ToSteamAudioTransform as _},
 };
@@ -17,7 +18,6 @@ use bevy_transform::prelude::*;
 use bevy_trenchbroom::{
     geometry::Brushes, physics::SceneCollidersReady, prelude::GenericMaterial3d,
 };
-use wildmatch::WildMatch;
 
 pub mod prelude {
     pub use crate::{TrenchBroomSteamAudioScenePlugin, TrenchBroomSteamAudioSettings};
@@ -29,40 +29,34 @@ impl Plugin for TrenchBroomSteamAudioScenePlugin {
     fn build(&self, app: &mut App) {
         app.add_observer(register_scene_ready_observer);
         app.init_resource::<TrenchBroomSteamAudioSettings>();
+        app.add_systems(
+            SteamAudioSchedule::from_app(app),
+            respawn_static_meshes
+                .in_set(SteamAudioSystems::MeshLifecycle)
+                // Not present if the Steam Audio context could not be created
+                .run_if(resource_exists::<SteamAudioRootScene>),
+        );
     }
 }
 
-#[derive(Resource, Debug, PartialEq)]
+/// Configures how TrenchBroom brushes become Steam Audio geometry.
+#[derive(Resource, Debug, Clone, PartialEq, Default)]
 pub struct TrenchBroomSteamAudioSettings {
-    pub material_mapping: Vec<(WildMatch, SteamAudioMaterial)>,
-}
-
-impl Default for TrenchBroomSteamAudioSettings {
-    fn default() -> Self {
-        Self::empty()
-            .map_material("*brick*", SteamAudioMaterial::BRICK)
-            .map_material("*concrete*", SteamAudioMaterial::CONCRETE)
-            .map_material("*ceramic*", SteamAudioMaterial::CERAMIC)
-            .map_material("*gravel*", SteamAudioMaterial::GRAVEL)
-            .map_material("*carpet*", SteamAudioMaterial::CARPET)
-            .map_material("*glass*", SteamAudioMaterial::GLASS)
-            .map_material("*plaster*", SteamAudioMaterial::PLASTER)
-            .map_material("*wood*", SteamAudioMaterial::WOOD)
-            .map_material("*metal*", SteamAudioMaterial::METAL)
-            .map_material("*rock*", SteamAudioMaterial::ROCK)
-    }
+    /// Maps the material path of each brush face to a [`SteamAudioMaterial`].
+    pub material_mapping: SteamAudioMaterialMapping,
 }
 
 impl TrenchBroomSteamAudioSettings {
+    /// Settings without material patterns. See [`SteamAudioMaterialMapping::empty`].
     pub fn empty() -> Self {
         Self {
-            material_mapping: Vec::new(),
+            material_mapping: SteamAudioMaterialMapping::empty(),
         }
     }
 
+    /// Appends a material pattern. See [`SteamAudioMaterialMapping::map_material`].
     pub fn map_material(mut self, pattern: impl AsRef<str>, material: SteamAudioMaterial) -> Self {
-        self.material_mapping
-            .push((WildMatch::new_case_insensitive(pattern.as_ref()), material));
+        self.material_mapping = self.material_mapping.map_material(pattern, material);
         self
     }
 }
@@ -91,13 +85,11 @@ fn handle_scene(
     brushes: Query<(), With<Brushes>>,
     mesh_handles: Query<(&Mesh3d, &GenericMaterial3d, &GlobalTransform)>,
     meshes: Res<Assets<Mesh>>,
-    names: Query<NameOrEntity>,
     mut root: ResMut<SteamAudioRootScene>,
     settings: Res<TrenchBroomSteamAudioSettings>,
     rigid_body: Query<(Option<&RigidBody>, Has<Static>, &Children)>,
     mut commands: Commands,
-) -> Result {
-    let mut errors = Vec::new();
+) {
     // All brushes are children of the scene root
     for entity in ready.collider_entities.iter().copied() {
         commands
@@ -107,7 +99,6 @@ fn handle_scene(
             .try_remove::<SteamAudioInstancedMesh>()
             .try_remove::<SteamAudioMaterial>()
             .try_insert(NotSteamAudioCollider);
-        let scene_entity_name = names.get(entity).unwrap();
         if !brushes.contains(entity) {
             continue;
         }
@@ -121,47 +112,46 @@ fn handle_scene(
             .unwrap_or(is_static);
 
         for entity in potential_materials {
-            let mat_entity_name = names.get(*entity).unwrap();
             let Ok((mesh, material, transform)) = mesh_handles.get(*entity) else {
                 continue;
             };
             let Some(material_name) = material.0.path() else {
                 // This shouldn't happen: TrenchBroom loads materials from disk
-                errors.push(format!(
-                    "{scene_entity_name}/{mat_entity_name}: Failed to get TrenchBroom material path"
-                ));
+                commands.trigger(SteamAudioError::MeshConversion {
+                    entity: *entity,
+                    error: MeshConversionError::MaterialWithoutPath,
+                });
                 continue;
             };
             let material_name = material_name.path().to_string_lossy();
             let Some(mesh) = meshes.get(mesh) else {
                 // This shouldn't happen: TrenchBroom directly creates and inserts its meshes
-                errors.push(format!(
-                    "{scene_entity_name}/{mat_entity_name}: Failed to get TrenchBroom mesh"
-                ));
+                commands.trigger(SteamAudioError::MeshConversion {
+                    entity: *entity,
+                    error: MeshConversionError::MeshNotLoaded,
+                });
                 continue;
             };
 
             let material = settings
                 .material_mapping
-                .iter()
-                .find(|(pattern, _)| pattern.matches(material_name.as_ref()))
-                .map(|(_, material)| *material)
-                .unwrap_or_default();
+                .material_for([material_name.as_ref()]);
             if is_static {
-                let mesh = mesh.clone().transformed_by(transform.compute_transform());
-                let audio_mesh = match mesh.to_steam_audio_mesh(&root, material.into()) {
+                let audio_mesh = match spawn_static_mesh(mesh, material, transform, &mut root) {
                     Ok(audio_mesh) => audio_mesh,
                     Err(err) => {
-                        errors.push(format!(
-                            "{scene_entity_name}/{mat_entity_name}: Failed to convert mesh to Steam Audio mesh: {err}"
-                        ));
+                        commands.trigger(SteamAudioError::MeshConversion {
+                            entity: *entity,
+                            error: err,
+                        });
                         continue;
                     }
                 };
-                root.add_static_mesh(audio_mesh.clone());
-                commands
-                    .entity(*entity)
-                    .try_insert((SteamAudioStaticMesh(audio_mesh), material));
+                commands.entity(*entity).try_insert((
+                    SteamAudioStaticMesh(audio_mesh),
+                    material,
+                    TrenchBroomStaticMesh,
+                ));
             } else {
                 let mut sub_scene = match audionimbus::Scene::try_new(
                     &STEAM_AUDIO_CONTEXT,
@@ -169,18 +159,20 @@ fn handle_scene(
                 ) {
                     Ok(sub_scene) => sub_scene,
                     Err(err) => {
-                        errors.push(format!(
-                            "{scene_entity_name}/{mat_entity_name}: Failed to create sub-scene: {err}"
-                        ));
+                        commands.trigger(SteamAudioError::SubScene {
+                            entity: *entity,
+                            error: err,
+                        });
                         continue;
                     }
                 };
                 let audio_mesh = match mesh.to_steam_audio_mesh(&sub_scene, material.into()) {
                     Ok(audio_mesh) => audio_mesh,
                     Err(err) => {
-                        errors.push(format!(
-                            "{scene_entity_name}/{mat_entity_name}: Failed to convert mesh to Steam Audio mesh: {err}"
-                        ));
+                        commands.trigger(SteamAudioError::MeshConversion {
+                            entity: *entity,
+                            error: err,
+                        });
                         continue;
                     }
                 };
@@ -193,16 +185,17 @@ fn handle_scene(
                     sub_scene: sub_scene.clone(),
                     transform,
                 };
-                let instanced_mesh = match audionimbus::InstancedMesh::try_new(
-                    &root,
-                    instanced_mesh_settings,
-                ) {
-                    Ok(instanced_mesh) => instanced_mesh,
-                    Err(err) => {
-                        errors.push(format!("{scene_entity_name}/{mat_entity_name}: Failed to create instanced mesh: {err}"));
-                        continue;
-                    }
-                };
+                let instanced_mesh =
+                    match audionimbus::InstancedMesh::try_new(&root, instanced_mesh_settings) {
+                        Ok(instanced_mesh) => instanced_mesh,
+                        Err(err) => {
+                            commands.trigger(SteamAudioError::InstancedMesh {
+                                entity: *entity,
+                                error: err,
+                            });
+                            continue;
+                        }
+                    };
                 root.add_instanced_mesh(instanced_mesh.clone());
                 commands
                     .entity(*entity)
@@ -216,9 +209,10 @@ fn handle_scene(
                 let gizmo = match SteamAudioGizmo::try_from(mesh) {
                     Ok(gizmo) => gizmo,
                     Err(err) => {
-                        errors.push(format!(
-                            "{scene_entity_name}/{mat_entity_name}: Failed to create Gizmo: {err}"
-                        ));
+                        commands.trigger(SteamAudioError::Gizmo {
+                            entity: *entity,
+                            error: err,
+                        });
                         continue;
                     }
                 };
@@ -226,9 +220,59 @@ fn handle_scene(
             }
         }
     }
-    if errors.is_empty() {
-        Ok(())
-    } else {
-        Err(BevyError::from(errors.join("\n")))
+}
+
+/// Marks the static brush meshes spawned by [`TrenchBroomSteamAudioScenePlugin`], which rebuilds them when they are queued, e.g. after moving.
+#[derive(Component)]
+struct TrenchBroomStaticMesh;
+
+/// Bakes the transform into the mesh and adds it to the root scene.
+fn spawn_static_mesh(
+    mesh: &Mesh,
+    material: SteamAudioMaterial,
+    transform: &GlobalTransform,
+    root: &mut audionimbus::Scene,
+) -> Result<audionimbus::StaticMesh, MeshConversionError> {
+    let mesh = mesh.clone().transformed_by(transform.compute_transform());
+    let audio_mesh = mesh.to_steam_audio_mesh(root, material.into())?;
+    root.add_static_mesh(audio_mesh.clone());
+    Ok(audio_mesh)
+}
+
+fn respawn_static_meshes(
+    queued: Query<
+        (Entity, &Mesh3d, &SteamAudioMaterial, &GlobalTransform),
+        (
+            With<InSteamAudioMeshSpawnQueue>,
+            With<TrenchBroomStaticMesh>,
+            Without<NotSteamAudioMesh>,
+        ),
+    >,
+    meshes: Res<Assets<Mesh>>,
+    mut root: ResMut<SteamAudioRootScene>,
+    mut commands: Commands,
+) {
+    for (entity, mesh, material, transform) in &queued {
+        commands
+            .entity(entity)
+            .try_remove::<InSteamAudioMeshSpawnQueue>();
+        let Some(mesh) = meshes.get(mesh) else {
+            commands.trigger(SteamAudioError::MeshConversion {
+                entity,
+                error: MeshConversionError::MeshNotLoaded,
+            });
+            continue;
+        };
+        match spawn_static_mesh(mesh, *material, transform, &mut root) {
+            // Replacing the old mesh removes it from the scene
+            Ok(audio_mesh) => {
+                commands
+                    .entity(entity)
+                    .try_insert(SteamAudioStaticMesh(audio_mesh));
+            }
+            Err(err) => {
+                commands.trigger(SteamAudioError::MeshConversion { entity, error: err });
+            }
+        }
     }
 }
@@ -74,7 +74,8 @@ fn setup(
                 RigidBody::Dynamic,
                 collider.clone(),
                 // We can optionally use a specific material for each collider instead of the default material.
-                SteamAudioMaterial::WOOD,
+                // Loading it from a file lets you tweak it while the app is running when hot reloading is enabled.
+                SteamAudioMaterialHandle(assets.load("materials/wood.steam_audio_material.ron")),
             ));
         }
     }
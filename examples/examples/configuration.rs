@@ -7,20 +7,31 @@ fn main() {
         .add_plugins((
             DefaultPlugins,
             SeedlingPlugin::default(),
-            SteamAudioPlugin::default(),
+            SteamAudioPlugin {
+                // The context settings control how Steam Audio itself runs.
+                // API validation is slow, so we only enable it in debug builds.
+                context: SteamAudioContextSettings {
+                    validation: cfg!(debug_assertions),
+                    ..default()
+                },
+                // Use `SteamAudioOutputMode::Panning` when playing over speakers instead of headphones.
+                output_mode: SteamAudioOutputMode::Binaural,
+                // SteamAudioQuality can be used to set global quality settings.
+                // This is also a resource that can be changed at runtime, e.g. in a settings menu.
+                // `SteamAudioQualityPreset` offers named presets such as `SteamAudioQualityPreset::High.quality()`.
+                quality: SteamAudioQuality {
+                    order: 3,
+                    frame_size: 1024,
+                    num_bounces: 32,
+                    direct: SteamAudioDirectQuality {
+                        max_num_occlusion_samples: 30,
+                    },
+                    ..default()
+                },
+                ..default()
+            },
             Mesh3dSteamAudioScenePlugin::default(),
         ))
-        // SteamAudioQuality can be used to set global quality settings.
-        // This resource can also be changed at runtime, e.g. in a settings menu.
-        .insert_resource(SteamAudioQuality {
-            order: 3,
-            frame_size: 1024,
-            num_bounces: 32,
-            direct: SteamAudioDirectQuality {
-                max_num_occlusion_samples: 30,
-            },
-            ..default()
-        })
         .add_systems(Startup, setup)
         .run();
 }
@@ -39,12 +50,12 @@ fn setup(
     commands.spawn((
         SamplePlayer::new(assets.load("selfless_courage.ogg")).looping(),
         SteamAudioPool,
-        // The `SteamAudioNode` tunes the parameters used when processing the audio.
-        sample_effects![SteamAudioNode {
+        // The `SteamAudioSource` tunes the parameters used when processing the audio.
+        SteamAudioSource {
             direct_gain: 3.0,
             reflection_gain: 0.5,
             ..default()
-        }],
+        },
         Transform::from_xyz(-1.5, 0.0, -3.0),
         Mesh3d(meshes.add(Sphere::new(0.2))),
         MeshMaterial3d(materials.add(Color::from(tailwind::GREEN_400))),
@@ -26,9 +26,11 @@ fn setup(
 ) {
     let audio_pos = Transform::from_xyz(40.0, 12.0, 0.0);
 
+    // The dungeon node has a `steam_audio` extras block, which makes it a static mesh with a generic material.
+    // See `GLTF_EXTRAS_KEY` for how to set it up in Blender.
     commands
         .spawn(SceneRoot(assets.load("dungeon.glb#Scene0")))
-        .observe(set_material);
+        .observe(generate_probes);
     commands.spawn((
         Camera3d::default(),
         EnvironmentMapLight {
@@ -59,21 +61,10 @@ fn setup(
     ));
 }
 
-fn set_material(
-    ready: On<SceneInstanceReady>,
-    children: Query<&Children>,
-    meshes: Query<(), (With<Mesh3d>, Without<SamplePlayer>)>,
-    mut commands: Commands,
+fn generate_probes(
+    _ready: On<SceneInstanceReady>,
     mut generate_probes: MessageWriter<GenerateProbes>,
 ) {
-    for child in children.iter_descendants(ready.entity) {
-        if meshes.contains(child) {
-            commands
-                .entity(child)
-                .try_insert((SteamAudioMaterial::GENERIC, Static));
-        }
-    }
-
     // Generate probes for pathing. This is optional.
     generate_probes.write(GenerateProbes::default());
 }
@@ -73,11 +73,11 @@ fn setup_loud_speaker(
         .try_insert((
             SamplePlayer::new(assets.load("selfless_courage.ogg")).looping(),
             SteamAudioPool,
-            sample_effects![SteamAudioNode {
+            SteamAudioSource {
                 direct_gain: 0.0,
                 reflection_gain: 0.0,
                 ..default()
-            }],
+            },
             PointLight {
                 shadows_enabled: true,
                 ..default()